@@ -0,0 +1,296 @@
+//! Merkle trees for agent-manager revenue epochs.
+//!
+//! The publisher snapshots holder balances off-chain, turns them into
+//! entitlements, and posts the tree root on-chain with `publish_revenue_epoch`.
+//! Each holder then claims with its index, amount and proof. The program uses
+//! the same `leaf_hash` and `verify` functions, so roots built here always
+//! match what it checks.
+
+use anchor_lang::solana_program::keccak::hashv;
+use anchor_lang::solana_program::pubkey::Pubkey;
+
+/// Domain separators so a leaf can never be passed off as an inner node
+const LEAF_PREFIX: &[u8] = &[0];
+const NODE_PREFIX: &[u8] = &[1];
+
+/// One holder's share of an epoch
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Entitlement {
+    pub claimant: Pubkey,
+    pub amount: u64,
+}
+
+/// Hash of the leaf for the claimant at `index`
+pub fn leaf_hash(index: u32, claimant: &Pubkey, amount: u64) -> [u8; 32] {
+    hashv(&[
+        LEAF_PREFIX,
+        &index.to_le_bytes(),
+        claimant.as_ref(),
+        &amount.to_le_bytes(),
+    ])
+    .to_bytes()
+}
+
+/// Hash of an inner node; children are sorted so proofs need no direction bits
+pub fn node_hash(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (left, right) = if a <= b { (a, b) } else { (b, a) };
+    hashv(&[NODE_PREFIX, left, right]).to_bytes()
+}
+
+/// Check that `leaf` is included in the tree with the given `root`
+pub fn verify(proof: &[[u8; 32]], root: &[u8; 32], leaf: [u8; 32]) -> bool {
+    let computed = proof
+        .iter()
+        .fold(leaf, |node, sibling| node_hash(&node, sibling));
+    computed == *root
+}
+
+/// Split `epoch_amount` across holders pro-rata to their snapshot balances.
+///
+/// Rounding dust stays unallocated and rolls over with the epoch's unclaimed
+/// funds. Holders whose share rounds to zero are left out.
+pub fn entitlements_from_balances(
+    balances: &[(Pubkey, u64)],
+    epoch_amount: u64,
+) -> Vec<Entitlement> {
+    let total: u128 = balances.iter().map(|(_, balance)| *balance as u128).sum();
+    if total == 0 {
+        return Vec::new();
+    }
+
+    balances
+        .iter()
+        .map(|(claimant, balance)| Entitlement {
+            claimant: *claimant,
+            amount: (epoch_amount as u128 * *balance as u128 / total) as u64,
+        })
+        .filter(|entitlement| entitlement.amount > 0)
+        .collect()
+}
+
+/// A complete tree over a list of entitlements, indexed by position
+pub struct MerkleTree {
+    entitlements: Vec<Entitlement>,
+    /// Layers from the leaves up to the root
+    layers: Vec<Vec<[u8; 32]>>,
+}
+
+impl MerkleTree {
+    pub fn new(entitlements: Vec<Entitlement>) -> Self {
+        assert!(
+            entitlements.len() <= u32::MAX as usize,
+            "too many entitlements for a single epoch"
+        );
+
+        let leaves: Vec<[u8; 32]> = entitlements
+            .iter()
+            .enumerate()
+            .map(|(index, entitlement)| {
+                leaf_hash(index as u32, &entitlement.claimant, entitlement.amount)
+            })
+            .collect();
+
+        let mut layers = vec![leaves];
+        while layers.last().is_some_and(|layer| layer.len() > 1) {
+            let next = layers
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => node_hash(left, right),
+                    // An unpaired node moves up unchanged
+                    [single] => *single,
+                    _ => unreachable!(),
+                })
+                .collect();
+            layers.push(next);
+        }
+
+        Self {
+            entitlements,
+            layers,
+        }
+    }
+
+    /// Root to publish on-chain; all zeroes for an empty tree
+    pub fn root(&self) -> [u8; 32] {
+        self.layers
+            .last()
+            .and_then(|layer| layer.first())
+            .copied()
+            .unwrap_or([0; 32])
+    }
+
+    pub fn len(&self) -> usize {
+        self.entitlements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entitlements.is_empty()
+    }
+
+    pub fn entitlements(&self) -> &[Entitlement] {
+        &self.entitlements
+    }
+
+    /// Sum of all entitlements, the SOL the publisher must fund the epoch with
+    pub fn total_amount(&self) -> u64 {
+        self.entitlements
+            .iter()
+            .map(|entitlement| entitlement.amount)
+            .sum()
+    }
+
+    /// Sibling hashes from the leaf at `index` up to the root
+    pub fn proof(&self, index: usize) -> Option<Vec<[u8; 32]>> {
+        if index >= self.len() {
+            return None;
+        }
+
+        let mut proof = Vec::new();
+        let mut position = index;
+        for layer in &self.layers[..self.layers.len() - 1] {
+            if let Some(sibling) = layer.get(position ^ 1) {
+                proof.push(*sibling);
+            }
+            position /= 2;
+        }
+        Some(proof)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree_of(count: usize) -> MerkleTree {
+        MerkleTree::new(
+            (0..count)
+                .map(|i| Entitlement {
+                    claimant: Pubkey::new_unique(),
+                    amount: 1_000 + i as u64,
+                })
+                .collect(),
+        )
+    }
+
+    fn leaf_at(tree: &MerkleTree, index: usize) -> [u8; 32] {
+        let entitlement = &tree.entitlements()[index];
+        leaf_hash(index as u32, &entitlement.claimant, entitlement.amount)
+    }
+
+    #[test]
+    fn every_proof_verifies_against_the_root() {
+        for count in 1..=17 {
+            let tree = tree_of(count);
+            for index in 0..count {
+                let proof = tree.proof(index).unwrap();
+                assert!(
+                    verify(&proof, &tree.root(), leaf_at(&tree, index)),
+                    "leaf {index} of {count}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn proofs_reject_wrong_amount_index_or_claimant() {
+        let tree = tree_of(5);
+        let root = tree.root();
+        let proof = tree.proof(3).unwrap();
+        let entitlement = &tree.entitlements()[3];
+
+        assert!(!verify(
+            &proof,
+            &root,
+            leaf_hash(3, &entitlement.claimant, entitlement.amount + 1)
+        ));
+        assert!(!verify(
+            &proof,
+            &root,
+            leaf_hash(2, &entitlement.claimant, entitlement.amount)
+        ));
+        assert!(!verify(
+            &proof,
+            &root,
+            leaf_hash(3, &Pubkey::new_unique(), entitlement.amount)
+        ));
+        assert!(tree.proof(5).is_none());
+    }
+
+    #[test]
+    fn unpaired_leaf_moves_up_unchanged() {
+        let tree = tree_of(3);
+        let (a, b, c) = (leaf_at(&tree, 0), leaf_at(&tree, 1), leaf_at(&tree, 2));
+
+        assert_eq!(tree.root(), node_hash(&node_hash(&a, &b), &c));
+        assert_eq!(tree.proof(2).unwrap(), vec![node_hash(&a, &b)]);
+        assert_eq!(tree.proof(0).unwrap(), vec![b, c]);
+    }
+
+    #[test]
+    fn single_leaf_is_its_own_root() {
+        let tree = tree_of(1);
+
+        assert_eq!(tree.root(), leaf_at(&tree, 0));
+        assert!(tree.proof(0).unwrap().is_empty());
+        assert!(verify(&[], &tree.root(), leaf_at(&tree, 0)));
+    }
+
+    #[test]
+    fn empty_tree_has_zero_root() {
+        let tree = MerkleTree::new(Vec::new());
+
+        assert!(tree.is_empty());
+        assert_eq!(tree.root(), [0; 32]);
+        assert!(tree.proof(0).is_none());
+    }
+
+    #[test]
+    fn leaf_and_node_hashes_are_domain_separated() {
+        let claimant = Pubkey::new_unique();
+        let leaf = leaf_hash(7, &claimant, 42);
+        let unprefixed = hashv(&[&7u32.to_le_bytes(), claimant.as_ref(), &42u64.to_le_bytes()]);
+        let as_node = hashv(&[
+            NODE_PREFIX,
+            &7u32.to_le_bytes(),
+            claimant.as_ref(),
+            &42u64.to_le_bytes(),
+        ]);
+        assert_ne!(leaf, unprefixed.to_bytes());
+        assert_ne!(leaf, as_node.to_bytes());
+
+        let (a, b) = (leaf_hash(0, &claimant, 1), leaf_hash(1, &claimant, 2));
+        let (left, right) = if a <= b { (a, b) } else { (b, a) };
+        assert_ne!(
+            node_hash(&a, &b),
+            hashv(&[LEAF_PREFIX, &left, &right]).to_bytes()
+        );
+        assert_eq!(node_hash(&a, &b), node_hash(&b, &a));
+    }
+
+    #[test]
+    fn entitlements_are_pro_rata_and_drop_zero_shares() {
+        let (x, y, z) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let entitlements = entitlements_from_balances(&[(x, 3), (y, 1), (z, 0)], 1_000);
+
+        assert_eq!(
+            entitlements,
+            vec![
+                Entitlement {
+                    claimant: x,
+                    amount: 750
+                },
+                Entitlement {
+                    claimant: y,
+                    amount: 250
+                },
+            ]
+        );
+        assert!(entitlements_from_balances(&[(x, 0)], 1_000).is_empty());
+    }
+}
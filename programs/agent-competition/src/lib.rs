@@ -0,0 +1,1358 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use agent_manager::{AgentState, AgentStatus, LendingPool, LendingPosition, SECONDS_PER_DAY};
+
+declare_id!("Compet1tion11111111111111111111111111111111");
+
+pub const MAX_BPS: u16 = 10_000;
+/// Upper bound on the organiser's cut of the prize pool
+pub const MAX_ORGANIZER_FEE_BPS: u16 = 2_000;
+/// Number of paid places a competition can have
+pub const MAX_PRIZE_PLACES: usize = 10;
+/// Keeps settlement within a single transaction's account limit
+pub const MAX_ENTRANTS: u32 = 24;
+/// How long after `start_at` / `end_at` a NAV snapshot can be recorded
+pub const SNAPSHOT_WINDOW: i64 = 60 * 60;
+
+#[program]
+pub mod agent_competition {
+    use super::*;
+
+    pub fn create_competition(
+        ctx: Context<CreateCompetition>,
+        competition_id: u64,
+        params: CompetitionParams,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        params.validate(now)?;
+        let CompetitionParams {
+            name,
+            entry_fee,
+            start_at,
+            end_at,
+            max_entrants,
+            organizer_fee_bps,
+            prize_split_bps,
+        } = params;
+
+        let competition = &mut ctx.accounts.competition;
+        competition.organizer = ctx.accounts.organizer.key();
+        competition.competition_id = competition_id;
+        competition.name = name.clone();
+        competition.entry_fee = entry_fee;
+        competition.start_at = start_at;
+        competition.end_at = end_at;
+        competition.max_entrants = max_entrants;
+        competition.organizer_fee_bps = organizer_fee_bps;
+        competition.prize_split_bps = prize_split_bps;
+        competition.entrant_count = 0;
+        competition.qualified_count = 0;
+        competition.open_entries = 0;
+        competition.prize_pool = 0;
+        competition.winner_count = 0;
+        competition.settled = false;
+        competition.bump = ctx.bumps.competition;
+
+        emit!(CompetitionCreatedEvent {
+            competition: competition.key(),
+            organizer: competition.organizer,
+            name,
+            entry_fee,
+            start_at,
+            end_at,
+            timestamp: now,
+        });
+
+        msg!("Competition created: {} entrants max, fee {}", max_entrants, entry_fee);
+        Ok(())
+    }
+
+    /// Sponsor the prize pool on top of the entry fees
+    pub fn fund_prize_pool(ctx: Context<FundPrizePool>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(!ctx.accounts.competition.settled, ErrorCode::AlreadySettled);
+
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.funder.to_account_info(),
+                    to: ctx.accounts.competition.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let competition = &mut ctx.accounts.competition;
+        competition.prize_pool = competition
+            .prize_pool
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+
+        emit!(PrizePoolFundedEvent {
+            competition: competition.key(),
+            funder: ctx.accounts.funder.key(),
+            amount,
+            prize_pool: competition.prize_pool,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Prize pool funded with {} lamports", amount);
+        Ok(())
+    }
+
+    /// Enter an agent, escrowing the entry fee in the competition account
+    pub fn enroll(ctx: Context<Enroll>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let competition = &ctx.accounts.competition;
+        require!(now < competition.start_at, ErrorCode::EnrollmentClosed);
+        require!(competition.entrant_count < competition.max_entrants, ErrorCode::CompetitionFull);
+        require!(
+            ctx.accounts.agent_state.state == AgentStatus::Active,
+            ErrorCode::AgentNotActive
+        );
+
+        let entry_fee = competition.entry_fee;
+        if entry_fee > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.authority.to_account_info(),
+                        to: ctx.accounts.competition.to_account_info(),
+                    },
+                ),
+                entry_fee,
+            )?;
+        }
+
+        let entry = &mut ctx.accounts.entry;
+        entry.competition = ctx.accounts.competition.key();
+        entry.agent_state = ctx.accounts.agent_state.key();
+        entry.owner = ctx.accounts.authority.key();
+        entry.start_nav = 0;
+        entry.start_deposits = 0;
+        entry.end_nav = 0;
+        entry.end_deposits = 0;
+        entry.start_recorded = false;
+        entry.end_recorded = false;
+        entry.return_bps = 0;
+        entry.rank = 0;
+        entry.prize = 0;
+        entry.bump = ctx.bumps.entry;
+
+        let competition = &mut ctx.accounts.competition;
+        competition.prize_pool = competition
+            .prize_pool
+            .checked_add(entry_fee)
+            .ok_or(ErrorCode::Overflow)?;
+        competition.entrant_count += 1;
+        competition.open_entries += 1;
+
+        emit!(AgentEnrolledEvent {
+            competition: competition.key(),
+            agent: entry.agent_state,
+            owner: entry.owner,
+            entry_fee,
+            timestamp: now,
+        });
+
+        msg!("Agent enrolled: {}/{}", competition.entrant_count, competition.max_entrants);
+        Ok(())
+    }
+
+    /// Snapshot an entrant's vault NAV. Permissionless; records the start NAV
+    /// in the window after `start_at` and the end NAV in the window after `end_at`.
+    /// NAV counts lamports supplied to the lending pool and budgets delegated
+    /// to sub-agents, and is net of the agent's loan, so borrowing and moving
+    /// funds around never show up as performance. Only vault lamports the
+    /// agent manager tracked are counted, so transfers straight to the vault
+    /// can't inflate it.
+    pub fn record_nav(ctx: Context<RecordNav>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let nav = measure_nav(
+            &ctx.accounts.agent_state,
+            &ctx.accounts.vault,
+            &ctx.accounts.lending_position,
+            ctx.accounts.lending_pool.as_deref(),
+        )?;
+        let deposits = ctx.accounts.agent_state.total_deposits;
+
+        let competition = &mut ctx.accounts.competition;
+        let entry = &mut ctx.accounts.entry;
+
+        let end = if in_window(now, competition.start_at) {
+            require!(!entry.start_recorded, ErrorCode::NavAlreadyRecorded);
+            entry.start_nav = nav;
+            entry.start_deposits = deposits;
+            entry.start_recorded = true;
+            false
+        } else if in_window(now, competition.end_at) {
+            require!(entry.start_recorded, ErrorCode::MissingStartNav);
+            require!(!entry.end_recorded, ErrorCode::NavAlreadyRecorded);
+            entry.end_nav = nav;
+            entry.end_deposits = deposits;
+            entry.end_recorded = true;
+            entry.return_bps = entry.performance_bps();
+            competition.qualified_count += 1;
+            true
+        } else {
+            return err!(ErrorCode::OutsideSnapshotWindow);
+        };
+
+        emit!(NavRecordedEvent {
+            competition: competition.key(),
+            agent: entry.agent_state,
+            nav,
+            end,
+            return_bps: entry.return_bps,
+            timestamp: now,
+        });
+
+        msg!("NAV recorded: {} lamports", nav);
+        Ok(())
+    }
+
+    /// Rank the qualified entries and assign prizes. Permissionless once the
+    /// end snapshot window has closed; qualified entries are passed as
+    /// remaining accounts, best return first.
+    pub fn settle_competition<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SettleCompetition<'info>>,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let competition_key = ctx.accounts.competition.key();
+        let competition = &ctx.accounts.competition;
+        require!(!competition.settled, ErrorCode::AlreadySettled);
+        require!(
+            now > competition.end_at.saturating_add(SNAPSHOT_WINDOW),
+            ErrorCode::CompetitionNotOver
+        );
+        require!(
+            ctx.remaining_accounts.len() == competition.qualified_count as usize,
+            ErrorCode::InvalidRanking
+        );
+
+        let mut entries: Vec<Account<'info, Entry>> = Vec::with_capacity(ctx.remaining_accounts.len());
+        for info in ctx.remaining_accounts.iter() {
+            require!(info.is_writable, ErrorCode::InvalidRanking);
+            let entry: Account<'info, Entry> = Account::try_from(info)?;
+            require_keys_eq!(entry.competition, competition_key, ErrorCode::InvalidRanking);
+            require!(entry.end_recorded, ErrorCode::InvalidRanking);
+            require!(
+                entries.iter().all(|ranked| ranked.key() != entry.key()),
+                ErrorCode::InvalidRanking
+            );
+            if let Some(previous) = entries.last() {
+                require!(previous.return_bps >= entry.return_bps, ErrorCode::InvalidRanking);
+            }
+            entries.push(entry);
+        }
+
+        let winner_count = entries.len().min(competition.prize_split_bps.len());
+        let organizer_fee = if winner_count > 0 {
+            bps_of(competition.prize_pool, competition.organizer_fee_bps)
+        } else {
+            0
+        };
+        let distributable = competition.prize_pool - organizer_fee;
+
+        // With fewer qualifiers than paid places the unused places are
+        // renormalised across the winners; rounding dust goes to first place
+        let split = &competition.prize_split_bps[..winner_count];
+        let split_total: u64 = split.iter().map(|bps| *bps as u64).sum();
+        let mut prizes: Vec<u64> = split
+            .iter()
+            .map(|bps| ((distributable as u128) * (*bps as u128) / (split_total as u128)) as u64)
+            .collect();
+        let allocated: u64 = prizes.iter().sum();
+        if let Some(first) = prizes.first_mut() {
+            *first += distributable - allocated;
+        }
+
+        for (index, entry) in entries.iter_mut().enumerate() {
+            entry.rank = (index + 1) as u16;
+            entry.prize = prizes.get(index).copied().unwrap_or(0);
+            entry.exit(&crate::ID)?;
+        }
+
+        if organizer_fee > 0 {
+            move_lamports(
+                &ctx.accounts.competition.to_account_info(),
+                &ctx.accounts.organizer.to_account_info(),
+                organizer_fee,
+            )?;
+        }
+
+        let competition = &mut ctx.accounts.competition;
+        competition.prize_pool = if winner_count > 0 { 0 } else { competition.prize_pool };
+        competition.winner_count = winner_count as u8;
+        competition.settled = true;
+
+        emit!(CompetitionSettledEvent {
+            competition: competition_key,
+            qualified: entries.len() as u32,
+            winners: winner_count as u8,
+            distributed: if winner_count > 0 { distributable } else { 0 },
+            organizer_fee,
+            timestamp: now,
+        });
+
+        msg!("Competition settled: {} winners", winner_count);
+        Ok(())
+    }
+
+    /// Pay out an entry's prize, or refund the entry fee when nobody
+    /// qualified, and close the entry
+    pub fn claim_prize(ctx: Context<ClaimPrize>) -> Result<()> {
+        let competition = &ctx.accounts.competition;
+        require!(competition.settled, ErrorCode::NotSettled);
+
+        let payout = if competition.winner_count == 0 {
+            competition.entry_fee
+        } else {
+            ctx.accounts.entry.prize
+        };
+
+        if payout > 0 {
+            move_lamports(
+                &ctx.accounts.competition.to_account_info(),
+                &ctx.accounts.owner.to_account_info(),
+                payout,
+            )?;
+        }
+
+        let competition = &mut ctx.accounts.competition;
+        competition.open_entries -= 1;
+        if competition.winner_count == 0 {
+            competition.prize_pool -= payout;
+        }
+
+        emit!(PrizeClaimedEvent {
+            competition: competition.key(),
+            agent: ctx.accounts.entry.agent_state,
+            owner: ctx.accounts.owner.key(),
+            rank: ctx.accounts.entry.rank,
+            amount: payout,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Prize claimed: {} lamports", payout);
+        Ok(())
+    }
+
+    /// Close a settled competition once every entry has been claimed. Any
+    /// unclaimed sponsorship and rounding dust go back to the organiser.
+    pub fn close_competition(ctx: Context<CloseCompetition>) -> Result<()> {
+        let competition = &ctx.accounts.competition;
+        require!(competition.settled, ErrorCode::NotSettled);
+        require!(competition.open_entries == 0, ErrorCode::EntriesOutstanding);
+
+        msg!("Competition closed");
+        Ok(())
+    }
+
+    /// Create the hill. Only the program's upgrade authority can, and it
+    /// becomes the hill authority.
+    pub fn initialize_hill(ctx: Context<InitializeHill>, reward_per_slot: u64) -> Result<()> {
+        let hill = &mut ctx.accounts.hill;
+        hill.authority = ctx.accounts.authority.key();
+        hill.king = Pubkey::default();
+        hill.king_owner = Pubkey::default();
+        hill.king_metric = 0;
+        hill.crowned_at = 0;
+        hill.crowned_slot = 0;
+        hill.last_reward_slot = 0;
+        hill.reward_per_slot = reward_per_slot;
+        hill.sponsor_pool = 0;
+        hill.total_rewards_paid = 0;
+        hill.reign_count = 0;
+        hill.bump = ctx.bumps.hill;
+
+        msg!("Hill initialized: {} lamports per slot", reward_per_slot);
+        Ok(())
+    }
+
+    /// Top up the pool that king rewards are paid from
+    pub fn fund_hill(ctx: Context<FundHill>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.sponsor.to_account_info(),
+                    to: ctx.accounts.hill.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let hill = &mut ctx.accounts.hill;
+        hill.sponsor_pool = hill.sponsor_pool.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+
+        emit!(HillFundedEvent {
+            sponsor: ctx.accounts.sponsor.key(),
+            amount,
+            sponsor_pool: hill.sponsor_pool,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Hill funded with {} lamports", amount);
+        Ok(())
+    }
+
+    /// Change the reward rate. A sitting king is first paid for the slots it
+    /// held at the old rate, so the change only applies going forward.
+    pub fn set_hill_reward(ctx: Context<SetHillReward>, reward_per_slot: u64) -> Result<()> {
+        let clock = Clock::get()?;
+        let king = ctx.accounts.hill.king;
+        if king != Pubkey::default() {
+            let king_owner = ctx
+                .accounts
+                .king_owner
+                .as_ref()
+                .ok_or(ErrorCode::MissingIncumbent)?;
+            pay_crown_reward(&mut ctx.accounts.hill, king_owner, &clock)?;
+        }
+
+        ctx.accounts.hill.reward_per_slot = reward_per_slot;
+
+        msg!("Hill reward set to {} lamports per slot", reward_per_slot);
+        Ok(())
+    }
+
+    /// Start tracking an agent's NAV for the hill. The agent can challenge
+    /// once the checkpoint is a day old.
+    pub fn open_hill_checkpoint(ctx: Context<OpenHillCheckpoint>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let nav = measure_nav(
+            &ctx.accounts.agent_state,
+            &ctx.accounts.vault,
+            &ctx.accounts.lending_position,
+            ctx.accounts.lending_pool.as_deref(),
+        )?;
+
+        let checkpoint = &mut ctx.accounts.checkpoint;
+        checkpoint.agent_state = ctx.accounts.agent_state.key();
+        checkpoint.bump = ctx.bumps.checkpoint;
+        checkpoint.record(nav, ctx.accounts.agent_state.total_deposits, now);
+
+        emit!(HillCheckpointEvent {
+            agent: checkpoint.agent_state,
+            nav,
+            timestamp: now,
+        });
+
+        msg!("Hill checkpoint opened at {} lamports", nav);
+        Ok(())
+    }
+
+    /// Move the checkpoint forward. Only allowed once the current one is a
+    /// day old, so an agent gets at most one fresh window per day.
+    pub fn refresh_hill_checkpoint(ctx: Context<RefreshHillCheckpoint>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            now.saturating_sub(ctx.accounts.checkpoint.recorded_at) >= SECONDS_PER_DAY,
+            ErrorCode::CheckpointTooRecent
+        );
+
+        let nav = measure_nav(
+            &ctx.accounts.agent_state,
+            &ctx.accounts.vault,
+            &ctx.accounts.lending_position,
+            ctx.accounts.lending_pool.as_deref(),
+        )?;
+        let checkpoint = &mut ctx.accounts.checkpoint;
+        checkpoint.record(nav, ctx.accounts.agent_state.total_deposits, now);
+
+        emit!(HillCheckpointEvent {
+            agent: checkpoint.agent_state,
+            nav,
+            timestamp: now,
+        });
+
+        msg!("Hill checkpoint refreshed at {} lamports", nav);
+        Ok(())
+    }
+
+    /// Take the crown by beating the incumbent's NAV gain. The gain is the
+    /// challenger's measured NAV growth since a checkpoint taken one to two
+    /// days ago, net of deposits. A king's gain stands for a day after it is
+    /// crowned; after that any positive gain takes the crown. The incumbent's
+    /// accrued reward is paid out before the crown changes hands.
+    pub fn challenge(ctx: Context<Challenge>) -> Result<()> {
+        let clock = Clock::get()?;
+        let challenger = &ctx.accounts.challenger;
+        require!(challenger.state == AgentStatus::Active, ErrorCode::AgentNotActive);
+        require_keys_neq!(challenger.key(), ctx.accounts.hill.king, ErrorCode::AlreadyKing);
+
+        let nav = measure_nav(
+            challenger,
+            &ctx.accounts.vault,
+            &ctx.accounts.lending_position,
+            ctx.accounts.lending_pool.as_deref(),
+        )?;
+        let challenger_metric =
+            ctx.accounts
+                .checkpoint
+                .nav_gain(nav, challenger.total_deposits, clock.unix_timestamp)?;
+        require!(challenger_metric > 0, ErrorCode::ChallengeFailed);
+
+        let previous_king = ctx.accounts.hill.king;
+        if previous_king != Pubkey::default() {
+            let hill = &ctx.accounts.hill;
+            if clock.unix_timestamp.saturating_sub(hill.crowned_at) < SECONDS_PER_DAY {
+                require!(challenger_metric > hill.king_metric, ErrorCode::ChallengeFailed);
+            }
+
+            let incumbent_owner = ctx
+                .accounts
+                .incumbent_owner
+                .as_ref()
+                .ok_or(ErrorCode::MissingIncumbent)?;
+            pay_crown_reward(&mut ctx.accounts.hill, incumbent_owner, &clock)?;
+        }
+
+        let challenger = &ctx.accounts.challenger;
+        let hill = &mut ctx.accounts.hill;
+        hill.king = challenger.key();
+        hill.king_owner = challenger.authority;
+        hill.king_metric = challenger_metric;
+        hill.crowned_at = clock.unix_timestamp;
+        hill.crowned_slot = clock.slot;
+        hill.last_reward_slot = clock.slot;
+        hill.reign_count += 1;
+
+        emit!(KingCrownedEvent {
+            king: challenger.key(),
+            previous_king,
+            metric: challenger_metric,
+            slot: clock.slot,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!("New king crowned with NAV gain {}", challenger_metric);
+        Ok(())
+    }
+
+    /// Collect the reward accrued by the sitting king
+    pub fn claim_crown_reward(ctx: Context<ClaimCrownReward>) -> Result<()> {
+        let clock = Clock::get()?;
+        let reward = accrue_crown_reward(&mut ctx.accounts.hill, clock.slot)?;
+        require!(reward > 0, ErrorCode::NothingToClaim);
+
+        move_lamports(
+            &ctx.accounts.hill.to_account_info(),
+            &ctx.accounts.authority.to_account_info(),
+            reward,
+        )?;
+
+        emit!(CrownRewardPaidEvent {
+            king: ctx.accounts.king.key(),
+            owner: ctx.accounts.authority.key(),
+            amount: reward,
+            timestamp: clock.unix_timestamp,
+        });
+
+        msg!("Crown reward claimed: {} lamports", reward);
+        Ok(())
+    }
+}
+
+fn in_window(now: i64, opens_at: i64) -> bool {
+    now >= opens_at && now <= opens_at.saturating_add(SNAPSHOT_WINDOW)
+}
+
+/// Vault NAV as the manager accounts for it: tracked free lamports plus
+/// sub-agent budgets and pool supply, less the agent's loan
+fn measure_nav(
+    agent_state: &AgentState,
+    vault: &AccountInfo,
+    position_info: &AccountInfo,
+    pool: Option<&LendingPool>,
+) -> Result<u64> {
+    let (supplied, debt) = lending_balance(position_info, pool)?;
+    let vault_balance = agent_state.tracked_vault_balance(vault.lamports());
+    let nav = agent_state
+        .free_balance(vault_balance)
+        .checked_add(agent_state.delegated_budget)
+        .and_then(|nav| nav.checked_add(supplied))
+        .ok_or(ErrorCode::Overflow)?
+        .saturating_sub(debt);
+    Ok(nav)
+}
+
+/// Reward owed to the king since the last payout, capped by the sponsor pool
+fn accrue_crown_reward(hill: &mut Hill, slot: u64) -> Result<u64> {
+    let slots_held = slot.saturating_sub(hill.last_reward_slot);
+    let reward = slots_held
+        .saturating_mul(hill.reward_per_slot)
+        .min(hill.sponsor_pool);
+
+    hill.last_reward_slot = slot;
+    hill.sponsor_pool -= reward;
+    hill.total_rewards_paid = hill
+        .total_rewards_paid
+        .checked_add(reward)
+        .ok_or(ErrorCode::Overflow)?;
+    Ok(reward)
+}
+
+/// Pay the sitting king's accrued reward to the owner it was crowned with
+fn pay_crown_reward<'info>(
+    hill: &mut Account<'info, Hill>,
+    king_owner: &AccountInfo<'info>,
+    clock: &Clock,
+) -> Result<()> {
+    require_keys_eq!(king_owner.key(), hill.king_owner, ErrorCode::MissingIncumbent);
+
+    let reward = accrue_crown_reward(hill, clock.slot)?;
+    if reward > 0 {
+        move_lamports(&hill.to_account_info(), king_owner, reward)?;
+        emit!(CrownRewardPaidEvent {
+            king: hill.king,
+            owner: king_owner.key(),
+            amount: reward,
+            timestamp: clock.unix_timestamp,
+        });
+    }
+    Ok(())
+}
+
+/// Pool assets supplied by an agent and its outstanding debt, both zero if
+/// it never opened a lending position
+fn lending_balance(position_info: &AccountInfo, pool: Option<&LendingPool>) -> Result<(u64, u64)> {
+    if position_info.owner != &agent_manager::ID || position_info.data_is_empty() {
+        return Ok((0, 0));
+    }
+
+    let position = LendingPosition::try_deserialize(&mut &position_info.try_borrow_data()?[..])?;
+    if position.supply_shares == 0 && position.debt_principal == 0 {
+        return Ok((0, 0));
+    }
+
+    let pool = pool.ok_or(ErrorCode::MissingLendingPool)?;
+    Ok((pool.assets_for_shares(position.supply_shares)?, position.current_debt(pool)?))
+}
+
+fn bps_of(amount: u64, bps: u16) -> u64 {
+    ((amount as u128) * (bps as u128) / (MAX_BPS as u128)) as u64
+}
+
+/// Move lamports out of an account owned by this program
+fn move_lamports(from: &AccountInfo, to: &AccountInfo, amount: u64) -> Result<()> {
+    let from_balance = from.lamports().checked_sub(amount).ok_or(ErrorCode::InsufficientFunds)?;
+    let to_balance = to.lamports().checked_add(amount).ok_or(ErrorCode::Overflow)?;
+    **from.try_borrow_mut_lamports()? = from_balance;
+    **to.try_borrow_mut_lamports()? = to_balance;
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(competition_id: u64)]
+pub struct CreateCompetition<'info> {
+    #[account(
+        init,
+        payer = organizer,
+        space = 8 + Competition::INIT_SPACE,
+        seeds = [b"competition", organizer.key().as_ref(), &competition_id.to_le_bytes()],
+        bump
+    )]
+    pub competition: Account<'info, Competition>,
+
+    #[account(mut)]
+    pub organizer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FundPrizePool<'info> {
+    #[account(
+        mut,
+        seeds = [b"competition", competition.organizer.as_ref(), &competition.competition_id.to_le_bytes()],
+        bump = competition.bump
+    )]
+    pub competition: Account<'info, Competition>,
+
+    #[account(mut)]
+    pub funder: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Enroll<'info> {
+    #[account(
+        mut,
+        seeds = [b"competition", competition.organizer.as_ref(), &competition.competition_id.to_le_bytes()],
+        bump = competition.bump
+    )]
+    pub competition: Account<'info, Competition>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Entry::INIT_SPACE,
+        seeds = [b"entry", competition.key().as_ref(), agent_state.key().as_ref()],
+        bump
+    )]
+    pub entry: Account<'info, Entry>,
+
+    #[account(
+        seeds = [b"agent", agent_state.authority.as_ref()],
+        bump = agent_state.bump,
+        seeds::program = agent_manager::ID,
+        has_one = authority
+    )]
+    pub agent_state: Account<'info, AgentState>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RecordNav<'info> {
+    #[account(
+        mut,
+        seeds = [b"competition", competition.organizer.as_ref(), &competition.competition_id.to_le_bytes()],
+        bump = competition.bump
+    )]
+    pub competition: Account<'info, Competition>,
+
+    #[account(
+        mut,
+        seeds = [b"entry", competition.key().as_ref(), agent_state.key().as_ref()],
+        bump = entry.bump,
+        has_one = competition,
+        has_one = agent_state
+    )]
+    pub entry: Account<'info, Entry>,
+
+    #[account(
+        seeds = [b"agent", agent_state.authority.as_ref()],
+        bump = agent_state.bump,
+        seeds::program = agent_manager::ID
+    )]
+    pub agent_state: Account<'info, AgentState>,
+
+    #[account(
+        seeds = [b"vault", agent_state.key().as_ref()],
+        bump = agent_state.vault_bump,
+        seeds::program = agent_manager::ID
+    )]
+    /// CHECK: agent-manager vault PDA, only its balance is read
+    pub vault: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"lending_position", agent_state.key().as_ref()],
+        bump,
+        seeds::program = agent_manager::ID
+    )]
+    /// CHECK: agent-manager lending position PDA, read only if it was opened
+    pub lending_position: UncheckedAccount<'info>,
+
+    /// Required when the agent has supplied to or borrowed from the pool
+    #[account(
+        seeds = [b"lending_pool"],
+        bump = lending_pool.bump,
+        seeds::program = agent_manager::ID
+    )]
+    pub lending_pool: Option<Account<'info, LendingPool>>,
+}
+
+#[derive(Accounts)]
+pub struct SettleCompetition<'info> {
+    #[account(
+        mut,
+        seeds = [b"competition", competition.organizer.as_ref(), &competition.competition_id.to_le_bytes()],
+        bump = competition.bump,
+        has_one = organizer
+    )]
+    pub competition: Account<'info, Competition>,
+
+    /// CHECK: receives the organiser fee, checked against the competition
+    #[account(mut)]
+    pub organizer: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimPrize<'info> {
+    #[account(
+        mut,
+        seeds = [b"competition", competition.organizer.as_ref(), &competition.competition_id.to_le_bytes()],
+        bump = competition.bump
+    )]
+    pub competition: Account<'info, Competition>,
+
+    #[account(
+        mut,
+        seeds = [b"entry", competition.key().as_ref(), entry.agent_state.as_ref()],
+        bump = entry.bump,
+        has_one = competition,
+        has_one = owner,
+        close = owner
+    )]
+    pub entry: Account<'info, Entry>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseCompetition<'info> {
+    #[account(
+        mut,
+        seeds = [b"competition", competition.organizer.as_ref(), &competition.competition_id.to_le_bytes()],
+        bump = competition.bump,
+        has_one = organizer,
+        close = organizer
+    )]
+    pub competition: Account<'info, Competition>,
+
+    #[account(mut)]
+    pub organizer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeHill<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Hill::INIT_SPACE,
+        seeds = [b"hill"],
+        bump
+    )]
+    pub hill: Account<'info, Hill>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::AgentCompetition>,
+
+    /// Only the upgrade authority can create the hill and become its authority
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(authority.key())
+            @ ErrorCode::UnauthorizedAdmin
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FundHill<'info> {
+    #[account(
+        mut,
+        seeds = [b"hill"],
+        bump = hill.bump
+    )]
+    pub hill: Account<'info, Hill>,
+
+    #[account(mut)]
+    pub sponsor: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetHillReward<'info> {
+    #[account(
+        mut,
+        seeds = [b"hill"],
+        bump = hill.bump,
+        has_one = authority
+    )]
+    pub hill: Account<'info, Hill>,
+
+    /// CHECK: receives the sitting king's accrued reward, checked against the hill
+    #[account(mut)]
+    pub king_owner: Option<UncheckedAccount<'info>>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct OpenHillCheckpoint<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + HillCheckpoint::INIT_SPACE,
+        seeds = [b"hill_checkpoint", agent_state.key().as_ref()],
+        bump
+    )]
+    pub checkpoint: Account<'info, HillCheckpoint>,
+
+    #[account(
+        seeds = [b"agent", agent_state.authority.as_ref()],
+        bump = agent_state.bump,
+        seeds::program = agent_manager::ID,
+        has_one = authority
+    )]
+    pub agent_state: Account<'info, AgentState>,
+
+    #[account(
+        seeds = [b"vault", agent_state.key().as_ref()],
+        bump = agent_state.vault_bump,
+        seeds::program = agent_manager::ID
+    )]
+    /// CHECK: agent-manager vault PDA, only its balance is read
+    pub vault: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"lending_position", agent_state.key().as_ref()],
+        bump,
+        seeds::program = agent_manager::ID
+    )]
+    /// CHECK: agent-manager lending position PDA, read only if it was opened
+    pub lending_position: UncheckedAccount<'info>,
+
+    /// Required when the agent has supplied to or borrowed from the pool
+    #[account(
+        seeds = [b"lending_pool"],
+        bump = lending_pool.bump,
+        seeds::program = agent_manager::ID
+    )]
+    pub lending_pool: Option<Account<'info, LendingPool>>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RefreshHillCheckpoint<'info> {
+    #[account(
+        mut,
+        seeds = [b"hill_checkpoint", agent_state.key().as_ref()],
+        bump = checkpoint.bump,
+        has_one = agent_state
+    )]
+    pub checkpoint: Account<'info, HillCheckpoint>,
+
+    #[account(
+        seeds = [b"agent", agent_state.authority.as_ref()],
+        bump = agent_state.bump,
+        seeds::program = agent_manager::ID,
+        has_one = authority
+    )]
+    pub agent_state: Account<'info, AgentState>,
+
+    #[account(
+        seeds = [b"vault", agent_state.key().as_ref()],
+        bump = agent_state.vault_bump,
+        seeds::program = agent_manager::ID
+    )]
+    /// CHECK: agent-manager vault PDA, only its balance is read
+    pub vault: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"lending_position", agent_state.key().as_ref()],
+        bump,
+        seeds::program = agent_manager::ID
+    )]
+    /// CHECK: agent-manager lending position PDA, read only if it was opened
+    pub lending_position: UncheckedAccount<'info>,
+
+    /// Required when the agent has supplied to or borrowed from the pool
+    #[account(
+        seeds = [b"lending_pool"],
+        bump = lending_pool.bump,
+        seeds::program = agent_manager::ID
+    )]
+    pub lending_pool: Option<Account<'info, LendingPool>>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Challenge<'info> {
+    #[account(
+        mut,
+        seeds = [b"hill"],
+        bump = hill.bump
+    )]
+    pub hill: Account<'info, Hill>,
+
+    #[account(
+        seeds = [b"agent", challenger.authority.as_ref()],
+        bump = challenger.bump,
+        seeds::program = agent_manager::ID,
+        has_one = authority
+    )]
+    pub challenger: Account<'info, AgentState>,
+
+    #[account(
+        seeds = [b"hill_checkpoint", challenger.key().as_ref()],
+        bump = checkpoint.bump,
+        constraint = checkpoint.agent_state == challenger.key() @ ErrorCode::StaleCheckpoint
+    )]
+    pub checkpoint: Account<'info, HillCheckpoint>,
+
+    #[account(
+        seeds = [b"vault", challenger.key().as_ref()],
+        bump = challenger.vault_bump,
+        seeds::program = agent_manager::ID
+    )]
+    /// CHECK: agent-manager vault PDA, only its balance is read
+    pub vault: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"lending_position", challenger.key().as_ref()],
+        bump,
+        seeds::program = agent_manager::ID
+    )]
+    /// CHECK: agent-manager lending position PDA, read only if it was opened
+    pub lending_position: UncheckedAccount<'info>,
+
+    /// Required when the agent has supplied to or borrowed from the pool
+    #[account(
+        seeds = [b"lending_pool"],
+        bump = lending_pool.bump,
+        seeds::program = agent_manager::ID
+    )]
+    pub lending_pool: Option<Account<'info, LendingPool>>,
+
+    /// CHECK: receives the incumbent's accrued reward, checked against the hill
+    #[account(mut)]
+    pub incumbent_owner: Option<UncheckedAccount<'info>>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimCrownReward<'info> {
+    #[account(
+        mut,
+        seeds = [b"hill"],
+        bump = hill.bump,
+        constraint = hill.king == king.key() @ ErrorCode::NotKing
+    )]
+    pub hill: Account<'info, Hill>,
+
+    #[account(
+        seeds = [b"agent", king.authority.as_ref()],
+        bump = king.bump,
+        seeds::program = agent_manager::ID,
+        has_one = authority
+    )]
+    pub king: Account<'info, AgentState>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+/// Terms of a competition, fixed at creation
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CompetitionParams {
+    pub name: String,
+    pub entry_fee: u64,
+    pub start_at: i64,
+    pub end_at: i64,
+    pub max_entrants: u32,
+    pub organizer_fee_bps: u16,
+    /// Share of the pool for each place, best first
+    pub prize_split_bps: Vec<u16>,
+}
+
+impl CompetitionParams {
+    pub fn validate(&self, now: i64) -> Result<()> {
+        require!(!self.name.is_empty() && self.name.len() <= 64, ErrorCode::InvalidName);
+        require!(
+            self.max_entrants > 0 && self.max_entrants <= MAX_ENTRANTS,
+            ErrorCode::InvalidMaxEntrants
+        );
+        require!(self.organizer_fee_bps <= MAX_ORGANIZER_FEE_BPS, ErrorCode::InvalidOrganizerFee);
+        require!(
+            !self.prize_split_bps.is_empty() && self.prize_split_bps.len() <= MAX_PRIZE_PLACES,
+            ErrorCode::InvalidPrizeSplit
+        );
+        require!(
+            self.prize_split_bps.iter().map(|bps| *bps as u32).sum::<u32>() == MAX_BPS as u32
+                && self.prize_split_bps.windows(2).all(|pair| pair[0] >= pair[1]),
+            ErrorCode::InvalidPrizeSplit
+        );
+        require!(
+            self.start_at > now && self.end_at > self.start_at,
+            ErrorCode::InvalidSchedule
+        );
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Competition {
+    pub organizer: Pubkey,
+    pub competition_id: u64,
+    #[max_len(64)]
+    pub name: String,
+    pub entry_fee: u64,
+    pub start_at: i64,
+    pub end_at: i64,
+    pub max_entrants: u32,
+    /// Organiser's cut of the prize pool, taken at settlement
+    pub organizer_fee_bps: u16,
+    /// Share of the pool for each place, best first, summing to `MAX_BPS`
+    #[max_len(10)]
+    pub prize_split_bps: Vec<u16>,
+    pub entrant_count: u32,
+    /// Entries with both a start and an end NAV snapshot
+    pub qualified_count: u32,
+    /// Entries not yet claimed after settlement
+    pub open_entries: u32,
+    /// Escrowed entry fees plus sponsorship
+    pub prize_pool: u64,
+    pub winner_count: u8,
+    pub settled: bool,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Entry {
+    pub competition: Pubkey,
+    pub agent_state: Pubkey,
+    /// Agent authority at enrollment, receives the prize
+    pub owner: Pubkey,
+    pub start_nav: u64,
+    pub start_deposits: u64,
+    pub end_nav: u64,
+    pub end_deposits: u64,
+    pub start_recorded: bool,
+    pub end_recorded: bool,
+    pub return_bps: i64,
+    /// 1-based place after settlement, 0 if unranked
+    pub rank: u16,
+    pub prize: u64,
+    pub bump: u8,
+}
+
+impl Entry {
+    /// Return over the competition in basis points. Net deposits made while
+    /// it ran are taken out of the end NAV so agents can't buy a better rank;
+    /// borrowing, sub-agent budgets and untracked transfers are already
+    /// accounted for in each NAV snapshot.
+    pub fn performance_bps(&self) -> i64 {
+        let net_deposits = self.end_deposits as i128 - self.start_deposits as i128;
+        let adjusted_end = self.end_nav as i128 - net_deposits;
+        if self.start_nav == 0 {
+            return 0;
+        }
+        let change = (adjusted_end - self.start_nav as i128) * MAX_BPS as i128 / self.start_nav as i128;
+        change.clamp(i64::MIN as i128, i64::MAX as i128) as i64
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Hill {
+    pub authority: Pubkey,
+    /// Agent state of the current king, default when the hill is empty
+    pub king: Pubkey,
+    /// King's authority when it took the crown, paid its reward when dethroned
+    pub king_owner: Pubkey,
+    /// King's NAV gain when it took the crown
+    pub king_metric: i64,
+    pub crowned_at: i64,
+    pub crowned_slot: u64,
+    /// Slot up to which the king's reward has been paid
+    pub last_reward_slot: u64,
+    pub reward_per_slot: u64,
+    /// Sponsor lamports not yet paid out as rewards
+    pub sponsor_pool: u64,
+    pub total_rewards_paid: u64,
+    pub reign_count: u64,
+    pub bump: u8,
+}
+
+/// An agent's NAV at the start of its hill measurement window
+#[account]
+#[derive(InitSpace)]
+pub struct HillCheckpoint {
+    pub agent_state: Pubkey,
+    pub nav: u64,
+    /// Agent's total deposits when recorded, so deposits don't count as gains
+    pub deposits: u64,
+    pub recorded_at: i64,
+    pub bump: u8,
+}
+
+impl HillCheckpoint {
+    pub fn record(&mut self, nav: u64, deposits: u64, now: i64) {
+        self.nav = nav;
+        self.deposits = deposits;
+        self.recorded_at = now;
+    }
+
+    /// NAV gained since the checkpoint, net of deposits. The checkpoint must
+    /// be one to two days old so every challenger is measured over a similar
+    /// window.
+    pub fn nav_gain(&self, nav: u64, deposits: u64, now: i64) -> Result<i64> {
+        let age = now.saturating_sub(self.recorded_at);
+        require!(
+            (SECONDS_PER_DAY..=2 * SECONDS_PER_DAY).contains(&age),
+            ErrorCode::StaleCheckpoint
+        );
+
+        let net_deposits = deposits as i128 - self.deposits as i128;
+        let gain = nav as i128 - net_deposits - self.nav as i128;
+        Ok(gain.clamp(i64::MIN as i128, i64::MAX as i128) as i64)
+    }
+}
+
+#[event]
+pub struct CompetitionCreatedEvent {
+    pub competition: Pubkey,
+    pub organizer: Pubkey,
+    pub name: String,
+    pub entry_fee: u64,
+    pub start_at: i64,
+    pub end_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PrizePoolFundedEvent {
+    pub competition: Pubkey,
+    pub funder: Pubkey,
+    pub amount: u64,
+    pub prize_pool: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AgentEnrolledEvent {
+    pub competition: Pubkey,
+    pub agent: Pubkey,
+    pub owner: Pubkey,
+    pub entry_fee: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct NavRecordedEvent {
+    pub competition: Pubkey,
+    pub agent: Pubkey,
+    pub nav: u64,
+    /// False for the start snapshot, true for the end snapshot
+    pub end: bool,
+    pub return_bps: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CompetitionSettledEvent {
+    pub competition: Pubkey,
+    pub qualified: u32,
+    pub winners: u8,
+    pub distributed: u64,
+    pub organizer_fee: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PrizeClaimedEvent {
+    pub competition: Pubkey,
+    pub agent: Pubkey,
+    pub owner: Pubkey,
+    pub rank: u16,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct HillFundedEvent {
+    pub sponsor: Pubkey,
+    pub amount: u64,
+    pub sponsor_pool: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct KingCrownedEvent {
+    pub king: Pubkey,
+    pub previous_king: Pubkey,
+    pub metric: i64,
+    pub slot: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct HillCheckpointEvent {
+    pub agent: Pubkey,
+    pub nav: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CrownRewardPaidEvent {
+    pub king: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Name must be 1-64 characters")]
+    InvalidName,
+    #[msg("Max entrants out of range")]
+    InvalidMaxEntrants,
+    #[msg("Organizer fee too high")]
+    InvalidOrganizerFee,
+    #[msg("Prize split must be descending and sum to 10000 bps")]
+    InvalidPrizeSplit,
+    #[msg("Competition must start in the future and end after it starts")]
+    InvalidSchedule,
+    #[msg("Invalid amount")]
+    InvalidAmount,
+    #[msg("Enrollment has closed")]
+    EnrollmentClosed,
+    #[msg("Competition is full")]
+    CompetitionFull,
+    #[msg("Agent is not active")]
+    AgentNotActive,
+    #[msg("No snapshot window is open")]
+    OutsideSnapshotWindow,
+    #[msg("NAV already recorded")]
+    NavAlreadyRecorded,
+    #[msg("Start NAV was never recorded")]
+    MissingStartNav,
+    #[msg("Competition has not finished")]
+    CompetitionNotOver,
+    #[msg("Ranked entries do not match the qualified entries")]
+    InvalidRanking,
+    #[msg("Competition already settled")]
+    AlreadySettled,
+    #[msg("Competition not settled")]
+    NotSettled,
+    #[msg("Entries still to be claimed")]
+    EntriesOutstanding,
+    #[msg("Agent already holds the crown")]
+    AlreadyKing,
+    #[msg("Challenger does not beat the incumbent")]
+    ChallengeFailed,
+    #[msg("Incumbent king and its owner must be provided")]
+    MissingIncumbent,
+    #[msg("Agent is not the king")]
+    NotKing,
+    #[msg("Nothing to claim")]
+    NothingToClaim,
+    #[msg("Only the program upgrade authority can do this")]
+    UnauthorizedAdmin,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+    #[msg("Insufficient funds")]
+    InsufficientFunds,
+    #[msg("Lending pool is required for an agent with a lending position")]
+    MissingLendingPool,
+    #[msg("Hill checkpoint must be one to two days old")]
+    StaleCheckpoint,
+    #[msg("Hill checkpoint is less than a day old")]
+    CheckpointTooRecent,
+}
@@ -1,8 +1,14 @@
 use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
 use anchor_spl::token::{self, Mint, Token, TokenAccount, MintTo};
 use anchor_spl::associated_token::AssociatedToken;
+use agent_registry::program::AgentRegistry;
+use agent_manager::AgentState;
 
-declare_id!("Factory11111111111111111111111111111111111");
+declare_id!("Factory111111111111111111111111111111111111");
+
+/// Current `FactoryState` layout version
+pub const FACTORY_STATE_VERSION: u8 = 1;
 
 #[program]
 pub mod agent_factory {
@@ -10,27 +16,36 @@ pub mod agent_factory {
 
     pub fn initialize(ctx: Context<Initialize>, creation_fee: u64) -> Result<()> {
         let factory_state = &mut ctx.accounts.factory_state;
+        factory_state.version = FACTORY_STATE_VERSION;
         factory_state.authority = ctx.accounts.authority.key();
         factory_state.treasury = ctx.accounts.treasury.key();
         factory_state.creation_fee = creation_fee;
         factory_state.total_agents_created = 0;
         factory_state.bump = ctx.bumps.factory_state;
+        factory_state.reserved = [0; 64];
 
         msg!("Factory initialized with fee: {} lamports", creation_fee);
         Ok(())
     }
 
+    /// Launch an agent's token and register it. The creator must already
+    /// have initialized its agent-manager state for this mint and wallet.
     pub fn create_agent(
         ctx: Context<CreateAgent>,
         name: String,
         symbol: String,
         purpose: String,
         agent_wallet: Pubkey,
+        agent_id: String,
     ) -> Result<()> {
-        require!(name.len() > 0 && name.len() <= 32, ErrorCode::InvalidName);
-        require!(symbol.len() > 0 && symbol.len() <= 10, ErrorCode::InvalidSymbol);
-        require!(purpose.len() > 0 && purpose.len() <= 200, ErrorCode::InvalidPurpose);
+        require!(!name.is_empty() && name.len() <= 32, ErrorCode::InvalidName);
+        require!(!symbol.is_empty() && symbol.len() <= 10, ErrorCode::InvalidSymbol);
+        require!(!purpose.is_empty() && purpose.len() <= 200, ErrorCode::InvalidPurpose);
         require!(agent_wallet != Pubkey::default(), ErrorCode::InvalidAgentWallet);
+        require!(
+            ctx.accounts.agent_state.agent_wallet == agent_wallet,
+            ErrorCode::AgentStateMismatch
+        );
 
         let factory_state = &mut ctx.accounts.factory_state;
 
@@ -68,6 +83,32 @@ pub mod agent_factory {
         // Increment counter
         factory_state.total_agents_created += 1;
 
+        // Register the agent so it has one canonical identity in the registry
+        let factory_bump = factory_state.bump;
+        let factory_seeds = &[b"factory".as_ref(), &[factory_bump]];
+        let signer = &[&factory_seeds[..]];
+
+        agent_registry::cpi::register_factory_agent(
+            CpiContext::new_with_signer(
+                ctx.accounts.registry_program.to_account_info(),
+                agent_registry::cpi::accounts::RegisterFactoryAgent {
+                    registry: ctx.accounts.registry.to_account_info(),
+                    link: ctx.accounts.link.to_account_info(),
+                    agent_state: ctx.accounts.agent_state.to_account_info(),
+                    config: ctx.accounts.registry_config.to_account_info(),
+                    factory: ctx.accounts.factory_state.to_account_info(),
+                    owner: ctx.accounts.creator.to_account_info(),
+                    payer: ctx.accounts.creator.to_account_info(),
+                    tombstone: ctx.accounts.registry_tombstone.to_account_info(),
+                    reserved_name: ctx.accounts.registry_reserved_name.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                },
+                signer,
+            ),
+            agent_id,
+            name.clone(),
+        )?;
+
         // Emit event
         emit!(AgentCreatedEvent {
             agent_pubkey: ctx.accounts.agent_state.key(),
@@ -93,6 +134,58 @@ pub mod agent_factory {
         msg!("Creation fee updated to: {} lamports", new_fee);
         Ok(())
     }
+
+    /// Realloc the pre-versioning factory account to the current layout
+    pub fn migrate_factory_state(ctx: Context<MigrateFactoryState>) -> Result<()> {
+        let info = ctx.accounts.factory_state.to_account_info();
+        require!(
+            info.data_len() == 8 + FactoryStateV0::INIT_SPACE,
+            ErrorCode::AlreadyMigrated
+        );
+
+        let legacy = {
+            let data = info.try_borrow_data()?;
+            require!(
+                data[..8] == FactoryState::DISCRIMINATOR,
+                ErrorCode::InvalidMigrationAccount
+            );
+            FactoryStateV0::deserialize(&mut &data[8..])?
+        };
+
+        require_keys_eq!(
+            legacy.authority,
+            ctx.accounts.authority.key(),
+            ErrorCode::InvalidMigrationAccount
+        );
+
+        let new_space = 8 + FactoryState::INIT_SPACE;
+        let rent_due = Rent::get()?
+            .minimum_balance(new_space)
+            .saturating_sub(info.lamports());
+        if rent_due > 0 {
+            let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
+                &ctx.accounts.authority.key(),
+                &info.key(),
+                rent_due,
+            );
+
+            anchor_lang::solana_program::program::invoke(
+                &transfer_ix,
+                &[
+                    ctx.accounts.authority.to_account_info(),
+                    info.clone(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+            )?;
+        }
+
+        info.realloc(new_space, true)?;
+        let migrated = legacy.migrate();
+        migrated.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+
+        msg!("Factory state migrated to version {}", FACTORY_STATE_VERSION);
+        Ok(())
+    }
 }
 
 #[derive(Accounts)]
@@ -116,7 +209,7 @@ pub struct Initialize<'info> {
 }
 
 #[derive(Accounts)]
-#[instruction(name: String, symbol: String, purpose: String, agent_wallet: Pubkey)]
+#[instruction(name: String, symbol: String, purpose: String, agent_wallet: Pubkey, agent_id: String)]
 pub struct CreateAgent<'info> {
     #[account(
         mut,
@@ -129,7 +222,7 @@ pub struct CreateAgent<'info> {
     pub creator: Signer<'info>,
 
     /// CHECK: Treasury account from factory state
-    #[account(mut)]
+    #[account(mut, address = factory_state.treasury @ ErrorCode::InvalidTreasury)]
     pub treasury_account: UncheckedAccount<'info>,
 
     #[account(
@@ -148,9 +241,34 @@ pub struct CreateAgent<'info> {
     )]
     pub creator_token_account: Account<'info, TokenAccount>,
 
-    /// CHECK: Agent state account initialized via CPI
+    /// The creator's agent-manager state, set up for this launch's token mint
+    #[account(
+        seeds = [b"agent", creator.key().as_ref()],
+        bump = agent_state.bump,
+        seeds::program = agent_manager::ID,
+        constraint = agent_state.authority == creator.key() @ ErrorCode::AgentStateMismatch,
+        constraint = agent_state.token_mint == token_mint.key() @ ErrorCode::AgentStateMismatch
+    )]
+    pub agent_state: Account<'info, AgentState>,
+
+    /// CHECK: Registry entry, created by the registry program
     #[account(mut)]
-    pub agent_state: UncheckedAccount<'info>,
+    pub registry: UncheckedAccount<'info>,
+
+    /// CHECK: Registry link for the agent state, created by the registry program
+    #[account(mut)]
+    pub link: UncheckedAccount<'info>,
+
+    /// CHECK: Registry config naming this factory, checked by the registry program
+    pub registry_config: UncheckedAccount<'info>,
+
+    /// CHECK: Tombstone for the agent id, checked by the registry program
+    pub registry_tombstone: UncheckedAccount<'info>,
+
+    /// CHECK: Reservation for the agent id, checked by the registry program
+    pub registry_reserved_name: UncheckedAccount<'info>,
+
+    pub registry_program: Program<'info, AgentRegistry>,
 
     pub token_program: Program<'info, Token>,
     pub associated_token_program: Program<'info, AssociatedToken>,
@@ -171,14 +289,58 @@ pub struct UpdateCreationFee<'info> {
     pub authority: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct MigrateFactoryState<'info> {
+    #[account(
+        mut,
+        seeds = [b"factory"],
+        bump,
+        owner = crate::ID
+    )]
+    /// CHECK: Legacy factory state, decoded and checked in the handler
+    pub factory_state: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
 #[account]
 #[derive(InitSpace)]
 pub struct FactoryState {
+    pub version: u8,
     pub authority: Pubkey,
     pub treasury: Pubkey,
     pub creation_fee: u64,
     pub total_agents_created: u64,
     pub bump: u8,
+    /// Spare space for new fields without another migration
+    pub reserved: [u8; 64],
+}
+
+/// `FactoryState` layout before versioning, kept to migrate the factory
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace)]
+pub struct FactoryStateV0 {
+    pub authority: Pubkey,
+    pub treasury: Pubkey,
+    pub creation_fee: u64,
+    pub total_agents_created: u64,
+    pub bump: u8,
+}
+
+impl FactoryStateV0 {
+    pub fn migrate(self) -> FactoryState {
+        FactoryState {
+            version: FACTORY_STATE_VERSION,
+            authority: self.authority,
+            treasury: self.treasury,
+            creation_fee: self.creation_fee,
+            total_agents_created: self.total_agents_created,
+            bump: self.bump,
+            reserved: [0; 64],
+        }
+    }
 }
 
 #[event]
@@ -203,4 +365,12 @@ pub enum ErrorCode {
     InvalidPurpose,
     #[msg("Agent wallet cannot be default pubkey")]
     InvalidAgentWallet,
+    #[msg("Account is already on the current layout")]
+    AlreadyMigrated,
+    #[msg("Account cannot be migrated")]
+    InvalidMigrationAccount,
+    #[msg("Agent state does not belong to this creator, mint and wallet")]
+    AgentStateMismatch,
+    #[msg("Treasury account does not match factory state")]
+    InvalidTreasury,
 }
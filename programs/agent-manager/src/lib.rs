@@ -1,8 +1,60 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use anchor_lang::Discriminator;
+use anchor_spl::token::{self, Burn, Token, TokenAccount, Transfer};
 
 declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
 
+/// Current `AgentState` layout version
+pub const AGENT_STATE_VERSION: u8 = 1;
+
+/// Prices are quoted in lamports per token base unit, scaled by 1e6
+pub const PRICE_SCALE: u64 = 1_000_000;
+
+/// Maximum age in seconds of a price feed quote used by cranks
+pub const MAX_PRICE_AGE: i64 = 60;
+
+/// Basis point denominator
+pub const MAX_BPS: u16 = 10_000;
+
+/// Upper bound on the profit share a leader can charge followers
+pub const MAX_COPY_FEE_BPS: u16 = 5_000;
+
+/// Open token positions a follower vault can hold at once
+pub const MAX_FOLLOWER_POSITIONS: usize = 8;
+
+/// Fixed-point scale of the lending pool borrow index
+pub const INDEX_SCALE: u128 = 1_000_000_000_000;
+
+/// Seconds per year used to convert annual lending rates
+pub const SECONDS_PER_YEAR: u128 = 31_536_000;
+
+/// Share of a borrower's debt that can be repaid in a single liquidation
+pub const LIQUIDATION_CLOSE_FACTOR_BPS: u16 = 5_000;
+
+/// Default delay before queued agent configuration changes take effect
+pub const DEFAULT_CONFIG_DELAY: i64 = 24 * 60 * 60;
+
+/// Longest delay an agent can require for configuration changes
+pub const MAX_CONFIG_DELAY: i64 = 30 * 24 * 60 * 60;
+
+/// Length of the rolling window used for daily PnL
+pub const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// Default pause duration after which the emergency exit opens
+pub const DEFAULT_EMERGENCY_GRACE_PERIOD: i64 = 14 * 24 * 60 * 60;
+
+/// Bounds on the emergency exit grace period an agent can configure
+pub const MIN_EMERGENCY_GRACE_PERIOD: i64 = 24 * 60 * 60;
+pub const MAX_EMERGENCY_GRACE_PERIOD: i64 = 90 * 24 * 60 * 60;
+
+/// Most claimants a single revenue epoch bitmap can track
+pub const MAX_EPOCH_CLAIMANTS: u32 = 80_000;
+
+/// Wrapped SOL mint, used to denote the vault's native SOL leg in trades
+pub const NATIVE_MINT: Pubkey = anchor_spl::token::spl_token::native_mint::ID;
+/// Upper bound on the share of performance fees a strategy author can take
+pub const MAX_STRATEGY_ROYALTY_BPS: u16 = 5_000;
+
 #[program]
 pub mod agent_manager {
     use super::*;
@@ -16,6 +68,7 @@ pub mod agent_manager {
     ) -> Result<()> {
         let agent_state = &mut ctx.accounts.agent_state;
 
+        agent_state.version = AGENT_STATE_VERSION;
         agent_state.authority = ctx.accounts.authority.key();
         agent_state.agent_wallet = agent_wallet;
         agent_state.token_mint = token_mint;
@@ -26,15 +79,58 @@ pub mod agent_manager {
         agent_state.total_trades = 0;
         agent_state.total_volume = 0;
         agent_state.revenue_pool = 0;
+        agent_state.order_count = 0;
+        agent_state.dca_count = 0;
+        agent_state.copy_fee_bps = 0;
+        agent_state.last_trade = TradeSnapshot::default();
+        agent_state.risk_config = RiskConfig::default();
+        agent_state.trading_whitelist = Vec::new();
+        agent_state.config_delay = DEFAULT_CONFIG_DELAY;
+        agent_state.config_change_count = 0;
+        agent_state.consecutive_losses = 0;
+        agent_state.daily_pnl = 0;
+        agent_state.day_start_balance = 0;
+        agent_state.day_started_at = 0;
+        agent_state.circuit_broken_at = 0;
+        agent_state.paused_at = 0;
+        agent_state.emergency_grace_period = DEFAULT_EMERGENCY_GRACE_PERIOD;
+        agent_state.total_deposits = 0;
+        agent_state.revenue_mode = RevenueMode::Distribute;
+        agent_state.buyback_pool = 0;
+        agent_state.total_burned = 0;
+        agent_state.revenue_epoch_count = 0;
         agent_state.bump = ctx.bumps.agent_state;
         agent_state.vault_bump = ctx.bumps.vault;
+        agent_state.parent = Pubkey::default();
+        agent_state.subagent_budget = 0;
+        agent_state.subagent_count = 0;
+        agent_state.delegated_budget = 0;
+        agent_state.strategy = Pubkey::default();
+        agent_state.strategy_royalty_bps = 0;
+        agent_state.strategy_expires_at = 0;
+        agent_state.strategy_royalties = 0;
+        agent_state.has_debt = false;
+        agent_state.open_orders = 0;
+        agent_state.open_dca_schedules = 0;
+        agent_state.follower_count = 0;
+        agent_state.retired_at = 0;
+        agent_state.open_revenue_epochs = 0;
+        agent_state.pending_config_changes = 0;
+        agent_state.governance_paused = false;
+        agent_state.vault_balance = 0;
 
         msg!("Agent initialized successfully");
         Ok(())
     }
 
+    /// Deposits are always tracked on the depositor's receipt so the
+    /// emergency exit can return them, and only active agents accept them
     pub fn deposit_funds(ctx: Context<DepositFunds>, amount: u64) -> Result<()> {
         require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(
+            ctx.accounts.agent_state.state == AgentStatus::Active,
+            ErrorCode::AgentNotActive
+        );
 
         // Transfer SOL from user to vault
         let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
@@ -52,6 +148,16 @@ pub mod agent_manager {
             ],
         )?;
 
+        let receipt = &mut ctx.accounts.receipt;
+        receipt.amount = receipt.amount.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+
+        let agent_state = &mut ctx.accounts.agent_state;
+        agent_state.total_deposits = agent_state
+            .total_deposits
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        agent_state.credit_vault(amount)?;
+
         emit!(FundsDepositedEvent {
             agent: ctx.accounts.agent_state.key(),
             depositor: ctx.accounts.depositor.key(),
@@ -93,73 +199,23 @@ pub mod agent_manager {
 
         // Note: In production, this would execute actual swap via Jupiter CPI
         // For MVP, we track the trade and calculate revenue share
-
-        // Calculate profit (if any)
-        if actual_output > amount {
-            let profit = actual_output.checked_sub(amount).unwrap();
-
-            // 1% platform fee from profit
-            let platform_fee = profit.checked_div(100).unwrap_or(0);
-
-            // Remaining 99% goes to revenue pool for token holders
-            let revenue_for_holders = profit.checked_sub(platform_fee).unwrap();
-
-            // Transfer platform fee to treasury
-            if platform_fee > 0 {
-                let agent_key = agent_state.key();
-                let seeds = &[
-                    b"vault",
-                    agent_key.as_ref(),
-                    &[agent_state.vault_bump],
-                ];
-                let signer = &[&seeds[..]];
-
-                let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
-                    &ctx.accounts.vault.key(),
-                    &ctx.accounts.treasury.key(),
-                    platform_fee,
-                );
-
-                anchor_lang::solana_program::program::invoke_signed(
-                    &transfer_ix,
-                    &[
-                        ctx.accounts.vault.to_account_info(),
-                        ctx.accounts.treasury.to_account_info(),
-                        ctx.accounts.system_program.to_account_info(),
-                    ],
-                    signer,
-                )?;
-
-                msg!("Platform fee collected: {} lamports", platform_fee);
-            }
-
-            // Add revenue to pool for token holders
-            agent_state.revenue_pool = agent_state.revenue_pool.checked_add(revenue_for_holders).unwrap();
-
-            msg!("Revenue added to pool: {} lamports", revenue_for_holders);
-        }
-
-        agent_state.total_trades += 1;
-        agent_state.total_volume = agent_state.total_volume.checked_add(amount).unwrap();
-
-        emit!(TradeExecutedEvent {
-            agent: ctx.accounts.agent_state.key(),
+        settle_trade(
+            agent_state,
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.treasury.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
             from_mint,
             to_mint,
-            amount_in: amount,
-            amount_out: actual_output,
-            timestamp: Clock::get()?.unix_timestamp,
-        });
-
-        msg!("Trade executed: {} -> {}", from_mint, to_mint);
-        msg!("Amount: {}, Output: {}", amount, actual_output);
-        Ok(())
+            amount,
+            actual_output,
+        )
     }
 
     pub fn claim_revenue_share(ctx: Context<ClaimRevenue>) -> Result<()> {
         let agent_state = &mut ctx.accounts.agent_state;
 
         require!(agent_state.revenue_pool > 0, ErrorCode::NoRevenueAvailable);
+        require!(!agent_state.has_debt, ErrorCode::DebtOutstanding);
 
         // Calculate user share based on token holdings
         let user_tokens = ctx.accounts.user_token_account.amount;
@@ -201,6 +257,7 @@ pub mod agent_manager {
         )?;
 
         agent_state.revenue_pool = agent_state.revenue_pool.checked_sub(user_share).unwrap();
+        agent_state.debit_vault(user_share);
 
         emit!(RevenueClaimedEvent {
             agent: ctx.accounts.agent_state.key(),
@@ -240,6 +297,7 @@ pub mod agent_manager {
         );
 
         agent_state.state = AgentStatus::Paused;
+        agent_state.paused_at = Clock::get()?.unix_timestamp;
 
         emit!(AgentStatusChangedEvent {
             agent: ctx.accounts.agent_state.key(),
@@ -258,6 +316,7 @@ pub mod agent_manager {
             agent_state.state == AgentStatus::Paused,
             ErrorCode::AgentAlreadyActive
         );
+        require!(!agent_state.governance_paused, ErrorCode::GovernancePaused);
 
         agent_state.state = AgentStatus::Active;
 
@@ -270,210 +329,4846 @@ pub mod agent_manager {
         msg!("Agent resumed");
         Ok(())
     }
-}
 
-#[derive(Accounts)]
-#[instruction(name: String, purpose: String)]
-pub struct InitializeAgent<'info> {
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + AgentState::INIT_SPACE,
-        seeds = [b"agent", authority.key().as_ref()],
-        bump
-    )]
-    pub agent_state: Account<'info, AgentState>,
+    /// Create the platform config. Only the program's upgrade authority can,
+    /// and it becomes the admin.
+    pub fn initialize_platform(ctx: Context<InitializePlatform>, treasury: Pubkey) -> Result<()> {
+        let platform = &mut ctx.accounts.platform;
+        platform.admin = ctx.accounts.admin.key();
+        platform.treasury = treasury;
+        platform.revenue_publisher = Pubkey::default();
+        platform.bump = ctx.bumps.platform;
 
-    #[account(
-        seeds = [b"vault", agent_state.key().as_ref()],
-        bump
-    )]
-    /// CHECK: PDA vault for agent funds
-    pub vault: UncheckedAccount<'info>,
+        msg!("Platform initialized with treasury: {}", treasury);
+        Ok(())
+    }
 
-    #[account(mut)]
-    pub authority: Signer<'info>,
+    pub fn initialize_price_feed(
+        ctx: Context<InitializePriceFeed>,
+        token_mint: Pubkey,
+        oracle: Pubkey,
+    ) -> Result<()> {
+        let price_feed = &mut ctx.accounts.price_feed;
+        price_feed.token_mint = token_mint;
+        price_feed.oracle = oracle;
+        price_feed.price = 0;
+        price_feed.updated_at = 0;
+        price_feed.bump = ctx.bumps.price_feed;
 
-    pub system_program: Program<'info, System>,
-}
+        msg!("Price feed initialized for mint: {}", token_mint);
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct DepositFunds<'info> {
-    #[account(
-        seeds = [b"agent", agent_state.authority.as_ref()],
-        bump = agent_state.bump
-    )]
-    pub agent_state: Account<'info, AgentState>,
+    pub fn update_price(ctx: Context<UpdatePrice>, price: u64) -> Result<()> {
+        require!(price > 0, ErrorCode::InvalidPrice);
 
-    #[account(
-        mut,
-        seeds = [b"vault", agent_state.key().as_ref()],
-        bump = agent_state.vault_bump
-    )]
-    /// CHECK: PDA vault
-    pub vault: UncheckedAccount<'info>,
+        let price_feed = &mut ctx.accounts.price_feed;
+        price_feed.price = price;
+        price_feed.updated_at = Clock::get()?.unix_timestamp;
 
-    #[account(mut)]
-    pub depositor: Signer<'info>,
+        emit!(PriceUpdatedEvent {
+            token_mint: price_feed.token_mint,
+            price,
+            timestamp: price_feed.updated_at,
+        });
 
-    pub system_program: Program<'info, System>,
-}
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct ExecuteTrade<'info> {
-    #[account(
-        mut,
-        seeds = [b"agent", agent_state.authority.as_ref()],
-        bump = agent_state.bump
-    )]
-    pub agent_state: Account<'info, AgentState>,
+    pub fn place_order(
+        ctx: Context<PlaceOrder>,
+        order_type: OrderType,
+        token_mint: Pubkey,
+        amount: u64,
+        trigger_price: u64,
+        keeper_tip: u64,
+        expires_at: i64,
+    ) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(trigger_price > 0, ErrorCode::InvalidPrice);
 
-    #[account(
-        mut,
-        seeds = [b"vault", agent_state.key().as_ref()],
-        bump = agent_state.vault_bump
-    )]
-    /// CHECK: PDA vault
-    pub vault: UncheckedAccount<'info>,
+        let now = Clock::get()?.unix_timestamp;
+        require!(expires_at == 0 || expires_at > now, ErrorCode::OrderExpired);
 
-    #[account(mut)]
-    /// CHECK: AGENT.FUN treasury wallet - validated in backend
-    pub treasury: UncheckedAccount<'info>,
+        let agent_state = &mut ctx.accounts.agent_state;
+        let order = &mut ctx.accounts.order;
 
-    pub signer: Signer<'info>,
+        order.agent = agent_state.key();
+        order.order_id = agent_state.order_count;
+        order.order_type = order_type.clone();
+        order.token_mint = token_mint;
+        order.amount = amount;
+        order.trigger_price = trigger_price;
+        order.keeper_tip = keeper_tip;
+        order.created_at = now;
+        order.expires_at = expires_at;
+        order.bump = ctx.bumps.order;
 
-    pub system_program: Program<'info, System>,
-}
+        agent_state.order_count += 1;
+        agent_state.open_orders = agent_state
+            .open_orders
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
 
-#[derive(Accounts)]
-pub struct ClaimRevenue<'info> {
-    #[account(
-        mut,
-        seeds = [b"agent", agent_state.authority.as_ref()],
-        bump = agent_state.bump
-    )]
-    pub agent_state: Account<'info, AgentState>,
+        // Escrow the keeper tip in the order account
+        if keeper_tip > 0 {
+            let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
+                &ctx.accounts.authority.key(),
+                &ctx.accounts.order.key(),
+                keeper_tip,
+            );
 
-    #[account(
-        mut,
-        seeds = [b"vault", agent_state.key().as_ref()],
-        bump = agent_state.vault_bump
-    )]
-    /// CHECK: PDA vault
-    pub vault: UncheckedAccount<'info>,
+            anchor_lang::solana_program::program::invoke(
+                &transfer_ix,
+                &[
+                    ctx.accounts.authority.to_account_info(),
+                    ctx.accounts.order.to_account_info(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+            )?;
+        }
 
-    #[account(mut)]
-    pub user: Signer<'info>,
+        emit!(OrderPlacedEvent {
+            agent: ctx.accounts.agent_state.key(),
+            order: ctx.accounts.order.key(),
+            order_type,
+            token_mint,
+            amount,
+            trigger_price,
+            keeper_tip,
+            timestamp: now,
+        });
 
-    #[account(
-        associated_token::mint = token_mint,
-        associated_token::authority = user
-    )]
-    pub user_token_account: Account<'info, TokenAccount>,
+        msg!("Order placed at trigger price {}", trigger_price);
+        Ok(())
+    }
 
-    #[account(address = agent_state.token_mint)]
-    pub token_mint: Account<'info, anchor_spl::token::Mint>,
+    pub fn cancel_order(ctx: Context<CancelOrder>) -> Result<()> {
+        let agent_state = &mut ctx.accounts.agent_state;
+        agent_state.open_orders = agent_state.open_orders.saturating_sub(1);
 
-    pub system_program: Program<'info, System>,
-}
+        emit!(OrderCancelledEvent {
+            agent: ctx.accounts.agent_state.key(),
+            order: ctx.accounts.order.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
 
-#[derive(Accounts)]
-pub struct UpdatePurpose<'info> {
-    #[account(
-        mut,
-        seeds = [b"agent", agent_state.authority.as_ref()],
-        bump = agent_state.bump,
-        has_one = authority
-    )]
-    pub agent_state: Account<'info, AgentState>,
+        msg!("Order cancelled");
+        Ok(())
+    }
 
-    pub authority: Signer<'info>,
-}
+    /// Fill a triggered order at the oracle price. Like `execute_trade`, no
+    /// swap is routed yet; the fill is booked against the agent with both legs
+    /// valued in lamports, so it moves no PnL, fees or revenue.
+    pub fn execute_order(ctx: Context<ExecuteOrder>) -> Result<()> {
+        let order = &ctx.accounts.order;
+        let now = Clock::get()?.unix_timestamp;
 
-#[derive(Accounts)]
-pub struct PauseAgent<'info> {
-    #[account(
-        mut,
-        seeds = [b"agent", agent_state.authority.as_ref()],
-        bump = agent_state.bump,
-        has_one = authority
-    )]
-    pub agent_state: Account<'info, AgentState>,
+        require!(
+            ctx.accounts.agent_state.state == AgentStatus::Active,
+            ErrorCode::AgentNotActive
+        );
+        require!(order.expires_at == 0 || now < order.expires_at, ErrorCode::OrderExpired);
 
-    pub authority: Signer<'info>,
-}
+        let price = fresh_price(&ctx.accounts.price_feed)?;
+        require!(order.is_triggered(price), ErrorCode::OrderNotTriggered);
 
-#[derive(Accounts)]
-pub struct ResumeAgent<'info> {
-    #[account(
-        mut,
-        seeds = [b"agent", agent_state.authority.as_ref()],
-        bump = agent_state.bump,
-        has_one = authority
-    )]
-    pub agent_state: Account<'info, AgentState>,
+        // Fill at the oracle price; buys spend vault SOL, sells return SOL
+        let (from_mint, to_mint, output, value) = match order.order_type {
+            OrderType::LimitBuy => {
+                require!(
+                    ctx.accounts.vault.lamports() >= order.amount,
+                    ErrorCode::InsufficientFunds
+                );
+                let output = quote_output(order.amount, price, true)?;
+                (NATIVE_MINT, order.token_mint, output, order.amount)
+            }
+            OrderType::StopLoss | OrderType::TakeProfit | OrderType::LimitSell => {
+                let output = quote_output(order.amount, price, false)?;
+                (order.token_mint, NATIVE_MINT, output, output)
+            }
+        };
 
-    pub authority: Signer<'info>,
-}
+        let amount = order.amount;
+        let keeper_tip = order.keeper_tip;
 
-#[account]
-#[derive(InitSpace)]
-pub struct AgentState {
-    pub authority: Pubkey,
-    pub agent_wallet: Pubkey,
-    pub token_mint: Pubkey,
-    pub vault: Pubkey,
-    #[max_len(32)]
-    pub name: String,
-    #[max_len(200)]
-    pub purpose: String,
-    pub state: AgentStatus,
-    pub total_trades: u64,
-    pub total_volume: u64,
-    pub revenue_pool: u64,
-    pub bump: u8,
-    pub vault_bump: u8,
-}
+        let agent_state = &mut ctx.accounts.agent_state;
+        agent_state.open_orders = agent_state.open_orders.saturating_sub(1);
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
-pub enum AgentStatus {
-    Active,
-    Paused,
-}
+        record_fill(
+            &mut ctx.accounts.agent_state,
+            &ctx.accounts.vault.to_account_info(),
+            from_mint,
+            to_mint,
+            value,
+        )?;
 
-#[event]
-pub struct FundsDepositedEvent {
-    pub agent: Pubkey,
-    pub depositor: Pubkey,
-    pub amount: u64,
-    pub timestamp: i64,
-}
+        // Pay the keeper tip out of the order escrow; rent returns to the authority on close
+        if keeper_tip > 0 {
+            **ctx.accounts.order.to_account_info().try_borrow_mut_lamports()? -= keeper_tip;
+            **ctx.accounts.keeper.to_account_info().try_borrow_mut_lamports()? += keeper_tip;
+        }
 
-#[event]
-pub struct TradeExecutedEvent {
-    pub agent: Pubkey,
-    pub from_mint: Pubkey,
-    pub to_mint: Pubkey,
-    pub amount_in: u64,
-    pub amount_out: u64,
-    pub timestamp: i64,
-}
+        emit!(OrderExecutedEvent {
+            agent: ctx.accounts.agent_state.key(),
+            order: ctx.accounts.order.key(),
+            keeper: ctx.accounts.keeper.key(),
+            price,
+            amount_in: amount,
+            amount_out: output,
+            keeper_tip,
+            timestamp: now,
+        });
 
-#[event]
-pub struct RevenueClaimedEvent {
-    pub agent: Pubkey,
-    pub user: Pubkey,
-    pub amount: u64,
-    pub timestamp: i64,
-}
+        msg!("Order executed at price {}", price);
+        Ok(())
+    }
 
-#[event]
-pub struct PurposeUpdatedEvent {
-    pub agent: Pubkey,
-    pub old_purpose: String,
-    pub new_purpose: String,
-    pub timestamp: i64,
-}
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_dca_schedule(
+        ctx: Context<CreateDcaSchedule>,
+        input_mint: Pubkey,
+        output_mint: Pubkey,
+        amount_per_interval: u64,
+        interval_seconds: i64,
+        total_cycles: u32,
+        max_slippage_bps: u16,
+        start_at: i64,
+    ) -> Result<()> {
+        require!(amount_per_interval > 0, ErrorCode::InvalidAmount);
+        require!(interval_seconds > 0 && total_cycles > 0, ErrorCode::InvalidDcaSchedule);
+        require!(
+            input_mint != output_mint && (input_mint == NATIVE_MINT || output_mint == NATIVE_MINT),
+            ErrorCode::InvalidDcaSchedule
+        );
+        require!(max_slippage_bps < MAX_BPS, ErrorCode::InvalidSlippage);
+
+        let now = Clock::get()?.unix_timestamp;
+        let agent_state = &mut ctx.accounts.agent_state;
+        let schedule = &mut ctx.accounts.schedule;
+
+        schedule.agent = agent_state.key();
+        schedule.schedule_id = agent_state.dca_count;
+        schedule.input_mint = input_mint;
+        schedule.output_mint = output_mint;
+        schedule.amount_per_interval = amount_per_interval;
+        schedule.interval_seconds = interval_seconds;
+        schedule.total_cycles = total_cycles;
+        schedule.completed_cycles = 0;
+        schedule.max_slippage_bps = max_slippage_bps;
+        schedule.next_execution_at = start_at.max(now);
+        schedule.bump = ctx.bumps.schedule;
+
+        agent_state.dca_count += 1;
+        agent_state.open_dca_schedules = agent_state
+            .open_dca_schedules
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        emit!(DcaScheduleCreatedEvent {
+            agent: agent_state.key(),
+            schedule: schedule.key(),
+            input_mint,
+            output_mint,
+            amount_per_interval,
+            interval_seconds,
+            total_cycles,
+            timestamp: now,
+        });
+
+        msg!("DCA schedule created: {} cycles every {}s", total_cycles, interval_seconds);
+        Ok(())
+    }
+
+    pub fn cancel_dca_schedule(ctx: Context<CancelDcaSchedule>) -> Result<()> {
+        let agent_state = &mut ctx.accounts.agent_state;
+        agent_state.open_dca_schedules = agent_state.open_dca_schedules.saturating_sub(1);
+
+        emit!(DcaScheduleClosedEvent {
+            agent: ctx.accounts.agent_state.key(),
+            schedule: ctx.accounts.schedule.key(),
+            completed_cycles: ctx.accounts.schedule.completed_cycles,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("DCA schedule cancelled");
+        Ok(())
+    }
+
+    /// Run a due DCA cycle against the oracle. The output is the oracle quote
+    /// less the schedule's slippage allowance, i.e. the worst fill the bounds
+    /// accept, and is never taken from the keeper. The cycle is booked with
+    /// `record_fill`.
+    pub fn crank_dca(ctx: Context<CrankDca>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let schedule = &ctx.accounts.schedule;
+
+        require!(
+            ctx.accounts.agent_state.state == AgentStatus::Active,
+            ErrorCode::AgentNotActive
+        );
+        require!(now >= schedule.next_execution_at, ErrorCode::DcaNotDue);
+
+        let buying = schedule.input_mint == NATIVE_MINT;
+        let token_mint = if buying { schedule.output_mint } else { schedule.input_mint };
+        require!(
+            ctx.accounts.price_feed.token_mint == token_mint,
+            ErrorCode::InvalidPriceFeed
+        );
+
+        let amount = schedule.amount_per_interval;
+        if buying {
+            require!(ctx.accounts.vault.lamports() >= amount, ErrorCode::InsufficientFunds);
+        }
+
+        let price = fresh_price(&ctx.accounts.price_feed)?;
+        let quote = quote_output(amount, price, buying)?;
+        let output = bps_of(quote, MAX_BPS - schedule.max_slippage_bps);
+        require!(output > 0, ErrorCode::SlippageExceeded);
+        let value = if buying { amount } else { output };
+
+        let (input_mint, output_mint) = (schedule.input_mint, schedule.output_mint);
+
+        record_fill(
+            &mut ctx.accounts.agent_state,
+            &ctx.accounts.vault.to_account_info(),
+            input_mint,
+            output_mint,
+            value,
+        )?;
+
+        let schedule = &mut ctx.accounts.schedule;
+        schedule.completed_cycles += 1;
+        schedule.next_execution_at = schedule
+            .next_execution_at
+            .checked_add(schedule.interval_seconds)
+            .ok_or(ErrorCode::MathOverflow)?
+            .max(now);
+
+        emit!(DcaCycleExecutedEvent {
+            agent: ctx.accounts.agent_state.key(),
+            schedule: schedule.key(),
+            cycle: schedule.completed_cycles,
+            amount_in: amount,
+            amount_out: output,
+            price,
+            timestamp: now,
+        });
+
+        msg!("DCA cycle {}/{} executed", schedule.completed_cycles, schedule.total_cycles);
+
+        // Close the schedule once every cycle has run
+        if schedule.completed_cycles >= schedule.total_cycles {
+            let agent_state = &mut ctx.accounts.agent_state;
+            agent_state.open_dca_schedules = agent_state.open_dca_schedules.saturating_sub(1);
+
+            emit!(DcaScheduleClosedEvent {
+                agent: ctx.accounts.agent_state.key(),
+                schedule: schedule.key(),
+                completed_cycles: schedule.completed_cycles,
+                timestamp: now,
+            });
+
+            ctx.accounts.schedule.close(ctx.accounts.authority.to_account_info())?;
+            msg!("DCA schedule completed");
+        }
+
+        Ok(())
+    }
+
+    pub fn follow_agent(
+        ctx: Context<FollowAgent>,
+        deposit: u64,
+        max_trade_amount: u64,
+        max_fee_bps: u16,
+    ) -> Result<()> {
+        require!(deposit > 0 && max_trade_amount > 0, ErrorCode::InvalidAmount);
+        require!(
+            ctx.accounts.leader_state.copy_fee_bps <= max_fee_bps,
+            ErrorCode::FeeTooHigh
+        );
+
+        let follower_state = &mut ctx.accounts.follower_state;
+        follower_state.leader = ctx.accounts.leader_state.key();
+        follower_state.owner = ctx.accounts.owner.key();
+        follower_state.max_trade_amount = max_trade_amount;
+        follower_state.max_fee_bps = max_fee_bps;
+        follower_state.last_mirrored_trade = ctx.accounts.leader_state.total_trades;
+        follower_state.total_trades = 0;
+        follower_state.total_volume = 0;
+        follower_state.fees_paid = 0;
+        follower_state.committed = 0;
+        follower_state.positions = Vec::new();
+        follower_state.bump = ctx.bumps.follower_state;
+        follower_state.vault_bump = ctx.bumps.follower_vault;
+
+        let leader_state = &mut ctx.accounts.leader_state;
+        leader_state.follower_count = leader_state
+            .follower_count
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.owner.key(),
+            &ctx.accounts.follower_vault.key(),
+            deposit,
+        );
+
+        anchor_lang::solana_program::program::invoke(
+            &transfer_ix,
+            &[
+                ctx.accounts.owner.to_account_info(),
+                ctx.accounts.follower_vault.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        emit!(AgentFollowedEvent {
+            leader: ctx.accounts.leader_state.key(),
+            follower: ctx.accounts.follower_state.key(),
+            owner: ctx.accounts.owner.key(),
+            deposit,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Following agent with {} lamports", deposit);
+        Ok(())
+    }
+
+    /// Replicate the leader's next trade into the follower vault at the
+    /// oracle price. Buys open or grow a position at cost; sells close part
+    /// of it and the leader fee is charged on the realised profit only.
+    /// Trades are mirrored strictly in order, and one the follower cannot
+    /// replicate (non-SOL pair, nothing to sell) is skipped with an event.
+    pub fn mirror_trade(ctx: Context<MirrorTrade>) -> Result<()> {
+        let leader_state = &ctx.accounts.leader_state;
+        let follower_state = &ctx.accounts.follower_state;
+        let leader_trade = leader_state.last_trade.clone();
+        let now = Clock::get()?.unix_timestamp;
+
+        require!(
+            leader_trade.trade_id > follower_state.last_mirrored_trade,
+            ErrorCode::NothingToMirror
+        );
+        require!(
+            leader_trade.trade_id == follower_state.last_mirrored_trade + 1,
+            ErrorCode::MirrorGap
+        );
+        require!(
+            leader_state.copy_fee_bps <= follower_state.max_fee_bps,
+            ErrorCode::FeeTooHigh
+        );
+        require!(leader_trade.vault_balance > 0, ErrorCode::InvalidAmount);
+
+        let buying = leader_trade.from_mint == NATIVE_MINT;
+        let selling = leader_trade.to_mint == NATIVE_MINT;
+        let token_mint = if buying { leader_trade.to_mint } else { leader_trade.from_mint };
+        let copy_fee_bps = leader_state.copy_fee_bps;
+        let leader_key = leader_state.key();
+        let follower_key = follower_state.key();
+
+        // Scale the SOL leg of the leader's trade by the follower/leader NAV ratio
+        let follower_balance = ctx.accounts.follower_vault.lamports();
+        let leader_value = if buying { leader_trade.amount_in } else { leader_trade.amount_out };
+        let mut value = mul_div(leader_value, follower_balance, leader_trade.vault_balance)?
+            .min(follower_state.max_trade_amount);
+        if buying {
+            value = value.min(follower_balance.saturating_sub(follower_state.committed));
+        }
+
+        let position = follower_state.positions.iter().position(|p| p.mint == token_mint);
+        let mirrorable = buying != selling
+            && value > 0
+            && (buying || position.is_some());
+
+        if !mirrorable {
+            ctx.accounts.follower_state.last_mirrored_trade = leader_trade.trade_id;
+
+            emit!(TradeMirrorSkippedEvent {
+                leader: leader_key,
+                follower: follower_key,
+                leader_trade_id: leader_trade.trade_id,
+                timestamp: now,
+            });
+
+            msg!("Skipped leader trade {}", leader_trade.trade_id);
+            return Ok(());
+        }
+
+        require!(
+            ctx.accounts.price_feed.token_mint == token_mint,
+            ErrorCode::InvalidPriceFeed
+        );
+        let price = fresh_price(&ctx.accounts.price_feed)?;
+
+        let follower_state = &mut ctx.accounts.follower_state;
+        let (amount_in, amount_out, fee) = if buying {
+            let tokens = quote_output(value, price, true)?;
+            require!(tokens > 0, ErrorCode::InvalidAmount);
+            follower_state.open_position(token_mint, tokens, value)?;
+
+            (value, tokens, 0)
+        } else {
+            let index = position.ok_or(ErrorCode::NothingToMirror)?;
+            let tokens = quote_output(value, price, true)?
+                .min(follower_state.positions[index].tokens);
+            require!(tokens > 0, ErrorCode::InvalidAmount);
+
+            let proceeds = quote_output(tokens, price, false)?;
+            let cost = follower_state.close_position(index, tokens)?;
+
+            // Followers pay the leader a share of their own realised profit
+            let fee = bps_of(proceeds.saturating_sub(cost), copy_fee_bps).min(follower_balance);
+
+            (tokens, proceeds, fee)
+        };
+
+        if fee > 0 {
+            transfer_signed(
+                &ctx.accounts.follower_vault.to_account_info(),
+                &ctx.accounts.leader_vault.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+                fee,
+                &[
+                    b"follower_vault",
+                    follower_key.as_ref(),
+                    &[ctx.accounts.follower_state.vault_bump],
+                ],
+            )?;
+
+            let leader_state = &mut ctx.accounts.leader_state;
+            leader_state.revenue_pool = leader_state
+                .revenue_pool
+                .checked_add(fee)
+                .ok_or(ErrorCode::MathOverflow)?;
+            leader_state.credit_vault(fee)?;
+        }
+
+        let follower_state = &mut ctx.accounts.follower_state;
+        follower_state.last_mirrored_trade = leader_trade.trade_id;
+        follower_state.total_trades += 1;
+        follower_state.total_volume = follower_state
+            .total_volume
+            .checked_add(value)
+            .ok_or(ErrorCode::MathOverflow)?;
+        follower_state.fees_paid = follower_state
+            .fees_paid
+            .checked_add(fee)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        emit!(TradeMirroredEvent {
+            leader: leader_key,
+            follower: follower_key,
+            leader_trade_id: leader_trade.trade_id,
+            from_mint: leader_trade.from_mint,
+            to_mint: leader_trade.to_mint,
+            amount_in,
+            amount_out,
+            leader_fee: fee,
+            timestamp: now,
+        });
+
+        msg!(
+            "Mirrored leader trade {}: {} -> {}",
+            leader_trade.trade_id,
+            amount_in,
+            amount_out
+        );
+        Ok(())
+    }
+
+    /// Catch a follower up to the leader's latest trade after the crank fell
+    /// behind or hit a trade it cannot fill. Every trade in between is
+    /// recorded as skipped.
+    pub fn resync_follower(ctx: Context<ResyncFollower>) -> Result<()> {
+        let latest = ctx.accounts.leader_state.last_trade.trade_id;
+        let follower_state = &mut ctx.accounts.follower_state;
+        require!(
+            latest > follower_state.last_mirrored_trade,
+            ErrorCode::NothingToMirror
+        );
+
+        let skipped = latest - follower_state.last_mirrored_trade;
+        let from_trade_id = follower_state.last_mirrored_trade + 1;
+        follower_state.last_mirrored_trade = latest;
+
+        emit!(FollowerResyncedEvent {
+            leader: follower_state.leader,
+            follower: follower_state.key(),
+            from_trade_id,
+            to_trade_id: latest,
+            skipped,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Follower resynced, skipped {} leader trades", skipped);
+        Ok(())
+    }
+
+    pub fn unfollow_agent(ctx: Context<UnfollowAgent>) -> Result<()> {
+        let leader_state = &mut ctx.accounts.leader_state;
+        leader_state.follower_count = leader_state.follower_count.saturating_sub(1);
+
+        let balance = ctx.accounts.follower_vault.lamports();
+        let follower_key = ctx.accounts.follower_state.key();
+
+        if balance > 0 {
+            transfer_signed(
+                &ctx.accounts.follower_vault.to_account_info(),
+                &ctx.accounts.owner.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+                balance,
+                &[
+                    b"follower_vault",
+                    follower_key.as_ref(),
+                    &[ctx.accounts.follower_state.vault_bump],
+                ],
+            )?;
+        }
+
+        emit!(AgentUnfollowedEvent {
+            leader: ctx.accounts.follower_state.leader,
+            follower: follower_key,
+            owner: ctx.accounts.owner.key(),
+            withdrawn: balance,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Unfollowed agent, withdrew {} lamports", balance);
+        Ok(())
+    }
+
+    pub fn initialize_lending_pool(
+        ctx: Context<InitializeLendingPool>,
+        params: LendingPoolParams,
+    ) -> Result<()> {
+        params.validate()?;
+
+        let pool = &mut ctx.accounts.pool;
+        pool.params = params;
+        pool.available_liquidity = 0;
+        pool.total_borrowed = 0;
+        pool.total_shares = 0;
+        pool.borrow_index = INDEX_SCALE;
+        pool.last_accrual_at = Clock::get()?.unix_timestamp;
+        pool.bump = ctx.bumps.pool;
+        pool.vault_bump = ctx.bumps.pool_vault;
+
+        msg!("Lending pool initialized");
+        Ok(())
+    }
+
+    pub fn open_lending_position(ctx: Context<OpenLendingPosition>) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+        position.agent = ctx.accounts.agent_state.key();
+        position.supply_shares = 0;
+        position.debt_principal = 0;
+        position.borrow_index = ctx.accounts.pool.borrow_index;
+        position.bump = ctx.bumps.position;
+
+        msg!("Lending position opened");
+        Ok(())
+    }
+
+    pub fn supply_to_pool(ctx: Context<AgentLending>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(!ctx.accounts.agent_state.has_debt, ErrorCode::DebtOutstanding);
+        require!(ctx.accounts.vault.lamports() >= amount, ErrorCode::InsufficientFunds);
+
+        let pool = &mut ctx.accounts.pool;
+        pool.accrue(Clock::get()?.unix_timestamp)?;
+        let shares = pool.shares_for_deposit(amount)?;
+
+        transfer_from_vault(
+            ctx.accounts.agent_state.key(),
+            ctx.accounts.agent_state.vault_bump,
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.pool_vault.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            amount,
+        )?;
+        ctx.accounts.agent_state.debit_vault(amount);
+
+        let pool = &mut ctx.accounts.pool;
+        pool.available_liquidity = pool
+            .available_liquidity
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        pool.total_shares = pool.total_shares.checked_add(shares).ok_or(ErrorCode::MathOverflow)?;
+
+        let position = &mut ctx.accounts.position;
+        position.supply_shares = position
+            .supply_shares
+            .checked_add(shares)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        emit!(PoolSuppliedEvent {
+            agent: ctx.accounts.agent_state.key(),
+            amount,
+            shares,
+            timestamp: pool.last_accrual_at,
+        });
+
+        msg!("Supplied {} lamports to lending pool", amount);
+        Ok(())
+    }
+
+    pub fn withdraw_from_pool(ctx: Context<AgentLending>, shares: u64) -> Result<()> {
+        require!(
+            shares > 0 && shares <= ctx.accounts.position.supply_shares,
+            ErrorCode::InvalidAmount
+        );
+
+        let pool = &mut ctx.accounts.pool;
+        pool.accrue(Clock::get()?.unix_timestamp)?;
+        let amount = pool.assets_for_shares(shares)?;
+        require!(amount <= pool.available_liquidity, ErrorCode::InsufficientLiquidity);
+
+        pool.available_liquidity -= amount;
+        pool.total_shares -= shares;
+        ctx.accounts.position.supply_shares -= shares;
+
+        transfer_signed(
+            &ctx.accounts.pool_vault.to_account_info(),
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            amount,
+            &[b"pool_vault", &[ctx.accounts.pool.vault_bump]],
+        )?;
+        ctx.accounts.agent_state.credit_vault(amount)?;
+
+        emit!(PoolWithdrawnEvent {
+            agent: ctx.accounts.agent_state.key(),
+            amount,
+            shares,
+            timestamp: ctx.accounts.pool.last_accrual_at,
+        });
+
+        msg!("Withdrew {} lamports from lending pool", amount);
+        Ok(())
+    }
+
+    pub fn borrow_from_pool(ctx: Context<AgentLending>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(
+            ctx.accounts.agent_state.state == AgentStatus::Active,
+            ErrorCode::AgentNotActive
+        );
+
+        let pool = &mut ctx.accounts.pool;
+        pool.accrue(Clock::get()?.unix_timestamp)?;
+        require!(amount <= pool.available_liquidity, ErrorCode::InsufficientLiquidity);
+
+        // Only the vault's own equity collateralises the loan: never borrowed
+        // funds, nor the pools owed to holders and the strategy author
+        let current_debt = ctx.accounts.position.current_debt(pool)?;
+        let debt = current_debt.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+        let collateral = ctx
+            .accounts
+            .agent_state
+            .free_balance(ctx.accounts.vault.lamports())
+            .saturating_sub(current_debt);
+        require!(
+            debt <= bps_of(collateral, pool.params.max_ltv_bps),
+            ErrorCode::InsufficientCollateral
+        );
+
+        pool.available_liquidity -= amount;
+        pool.total_borrowed = pool
+            .total_borrowed
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        let position = &mut ctx.accounts.position;
+        position.debt_principal = debt;
+        position.borrow_index = pool.borrow_index;
+        ctx.accounts.agent_state.has_debt = true;
+
+        transfer_signed(
+            &ctx.accounts.pool_vault.to_account_info(),
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            amount,
+            &[b"pool_vault", &[ctx.accounts.pool.vault_bump]],
+        )?;
+        ctx.accounts.agent_state.credit_vault(amount)?;
+
+        emit!(PoolBorrowedEvent {
+            agent: ctx.accounts.agent_state.key(),
+            amount,
+            total_debt: debt,
+            timestamp: ctx.accounts.pool.last_accrual_at,
+        });
+
+        msg!("Borrowed {} lamports from lending pool", amount);
+        Ok(())
+    }
+
+    pub fn repay_loan(ctx: Context<AgentLending>, amount: u64) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        pool.accrue(Clock::get()?.unix_timestamp)?;
+
+        let debt = ctx.accounts.position.current_debt(pool)?;
+        let repaid = amount.min(debt);
+        require!(repaid > 0, ErrorCode::InvalidAmount);
+        require!(ctx.accounts.vault.lamports() >= repaid, ErrorCode::InsufficientFunds);
+
+        pool.available_liquidity = pool
+            .available_liquidity
+            .checked_add(repaid)
+            .ok_or(ErrorCode::MathOverflow)?;
+        pool.total_borrowed = pool.total_borrowed.saturating_sub(repaid);
+
+        let position = &mut ctx.accounts.position;
+        position.debt_principal = debt - repaid;
+        position.borrow_index = pool.borrow_index;
+        ctx.accounts.agent_state.has_debt = debt > repaid;
+
+        transfer_from_vault(
+            ctx.accounts.agent_state.key(),
+            ctx.accounts.agent_state.vault_bump,
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.pool_vault.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            repaid,
+        )?;
+        ctx.accounts.agent_state.debit_vault(repaid);
+
+        emit!(LoanRepaidEvent {
+            agent: ctx.accounts.agent_state.key(),
+            amount: repaid,
+            remaining_debt: debt - repaid,
+            timestamp: ctx.accounts.pool.last_accrual_at,
+        });
+
+        msg!("Repaid {} lamports", repaid);
+        Ok(())
+    }
+
+    pub fn liquidate(ctx: Context<Liquidate>, repay_amount: u64) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        pool.accrue(Clock::get()?.unix_timestamp)?;
+
+        // Collateral is valued as when borrowing; only free funds can be seized
+        let debt = ctx.accounts.position.current_debt(pool)?;
+        let free_balance = ctx.accounts.agent_state.free_balance(ctx.accounts.vault.lamports());
+        let collateral = free_balance.saturating_sub(debt);
+        require!(
+            debt > bps_of(collateral, pool.params.liquidation_threshold_bps),
+            ErrorCode::PositionHealthy
+        );
+
+        // Liquidators may repay at most the close factor of the debt per call
+        let repaid = repay_amount.min(bps_of(debt, LIQUIDATION_CLOSE_FACTOR_BPS).max(1)).min(debt);
+        require!(repaid > 0, ErrorCode::InvalidAmount);
+        let seized = bps_of(repaid, MAX_BPS + pool.params.liquidation_bonus_bps).min(free_balance);
+
+        pool.available_liquidity = pool
+            .available_liquidity
+            .checked_add(repaid)
+            .ok_or(ErrorCode::MathOverflow)?;
+        pool.total_borrowed = pool.total_borrowed.saturating_sub(repaid);
+
+        let position = &mut ctx.accounts.position;
+        position.debt_principal = debt - repaid;
+        position.borrow_index = pool.borrow_index;
+        ctx.accounts.agent_state.has_debt = debt > repaid;
+
+        let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
+            &ctx.accounts.liquidator.key(),
+            &ctx.accounts.pool_vault.key(),
+            repaid,
+        );
+
+        anchor_lang::solana_program::program::invoke(
+            &transfer_ix,
+            &[
+                ctx.accounts.liquidator.to_account_info(),
+                ctx.accounts.pool_vault.to_account_info(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+        )?;
+
+        transfer_from_vault(
+            ctx.accounts.agent_state.key(),
+            ctx.accounts.agent_state.vault_bump,
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.liquidator.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            seized,
+        )?;
+        ctx.accounts.agent_state.debit_vault(seized);
+
+        emit!(PositionLiquidatedEvent {
+            agent: ctx.accounts.agent_state.key(),
+            liquidator: ctx.accounts.liquidator.key(),
+            repaid,
+            seized,
+            remaining_debt: debt - repaid,
+            timestamp: ctx.accounts.pool.last_accrual_at,
+        });
+
+        msg!("Liquidated {} lamports of debt, seized {}", repaid, seized);
+        Ok(())
+    }
+
+    pub fn initialize_governance(
+        ctx: Context<InitializeGovernance>,
+        quorum_bps: u16,
+        voting_period: i64,
+        timelock: i64,
+        proposal_threshold: u64,
+    ) -> Result<()> {
+        require!(quorum_bps > 0 && quorum_bps <= MAX_BPS, ErrorCode::InvalidGovernanceConfig);
+        require!(voting_period > 0 && timelock >= 0, ErrorCode::InvalidGovernanceConfig);
+
+        let governance = &mut ctx.accounts.governance;
+        governance.agent = ctx.accounts.agent_state.key();
+        governance.quorum_bps = quorum_bps;
+        governance.voting_period = voting_period;
+        governance.timelock = timelock;
+        governance.proposal_threshold = proposal_threshold;
+        governance.proposal_count = 0;
+        governance.open_proposals = 0;
+        governance.bump = ctx.bumps.governance;
+
+        msg!("Governance initialized: quorum {} bps", quorum_bps);
+        Ok(())
+    }
+
+    pub fn create_voter_escrow(ctx: Context<CreateVoterEscrow>) -> Result<()> {
+        let voter = &mut ctx.accounts.voter;
+        voter.agent = ctx.accounts.agent_state.key();
+        voter.owner = ctx.accounts.owner.key();
+        voter.amount = 0;
+        voter.locked_until = 0;
+        voter.deposited_at = 0;
+        voter.bump = ctx.bumps.voter;
+
+        Ok(())
+    }
+
+    pub fn deposit_voting_tokens(ctx: Context<DepositVotingTokens>, amount: u64) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.owner_token_account.to_account_info(),
+                    to: ctx.accounts.escrow_tokens.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let voter = &mut ctx.accounts.voter;
+        voter.amount = voter.amount.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+        voter.deposited_at = Clock::get()?.unix_timestamp;
+
+        msg!("Escrowed {} voting tokens", amount);
+        Ok(())
+    }
+
+    pub fn withdraw_voting_tokens(ctx: Context<WithdrawVotingTokens>, amount: u64) -> Result<()> {
+        let voter = &ctx.accounts.voter;
+        require!(amount > 0 && amount <= voter.amount, ErrorCode::InvalidAmount);
+        require!(
+            Clock::get()?.unix_timestamp >= voter.locked_until,
+            ErrorCode::VotingTokensLocked
+        );
+
+        let agent_key = voter.agent;
+        let owner_key = voter.owner;
+        let seeds = &[b"voter", agent_key.as_ref(), owner_key.as_ref(), &[voter.bump]];
+        let signer = &[&seeds[..]];
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.escrow_tokens.to_account_info(),
+                    to: ctx.accounts.owner_token_account.to_account_info(),
+                    authority: ctx.accounts.voter.to_account_info(),
+                },
+                signer,
+            ),
+            amount,
+        )?;
+
+        let voter = &mut ctx.accounts.voter;
+        voter.amount -= amount;
+
+        msg!("Withdrew {} voting tokens", amount);
+        Ok(())
+    }
+
+    pub fn create_proposal(ctx: Context<CreateProposal>, action: GovernanceAction) -> Result<()> {
+        action.validate()?;
+
+        let governance = &mut ctx.accounts.governance;
+        require!(
+            ctx.accounts.voter.amount >= governance.proposal_threshold,
+            ErrorCode::BelowProposalThreshold
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        let quorum_votes = bps_of(ctx.accounts.token_mint.supply, governance.quorum_bps);
+
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.agent = governance.agent;
+        proposal.proposal_id = governance.proposal_count;
+        proposal.proposer = ctx.accounts.proposer.key();
+        proposal.action = action.clone();
+        proposal.votes_for = 0;
+        proposal.votes_against = 0;
+        proposal.quorum_votes = quorum_votes;
+        proposal.voting_ends_at = now
+            .checked_add(governance.voting_period)
+            .ok_or(ErrorCode::MathOverflow)?;
+        proposal.eta = proposal
+            .voting_ends_at
+            .checked_add(governance.timelock)
+            .ok_or(ErrorCode::MathOverflow)?;
+        proposal.created_at = now;
+        proposal.executed = false;
+        proposal.bump = ctx.bumps.proposal;
+
+        governance.proposal_count += 1;
+        governance.open_proposals = governance
+            .open_proposals
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        emit!(ProposalCreatedEvent {
+            agent: proposal.agent,
+            proposal: proposal.key(),
+            proposer: proposal.proposer,
+            action,
+            voting_ends_at: proposal.voting_ends_at,
+            eta: proposal.eta,
+            timestamp: now,
+        });
+
+        msg!("Proposal {} created", proposal.proposal_id);
+        Ok(())
+    }
+
+    pub fn cast_vote(ctx: Context<CastVote>, support: bool) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let proposal = &mut ctx.accounts.proposal;
+        require!(now < proposal.voting_ends_at, ErrorCode::VotingClosed);
+
+        // Escrow only shrinks after the proposal exists, so the balance never
+        // exceeds the holder's weight when it was created
+        require!(
+            ctx.accounts.voter.deposited_at < proposal.created_at,
+            ErrorCode::VotingWeightTooNew
+        );
+        let weight = ctx.accounts.voter.amount;
+        require!(weight > 0, ErrorCode::NoTokensHeld);
+
+        if support {
+            proposal.votes_for = proposal
+                .votes_for
+                .checked_add(weight)
+                .ok_or(ErrorCode::MathOverflow)?;
+        } else {
+            proposal.votes_against = proposal
+                .votes_against
+                .checked_add(weight)
+                .ok_or(ErrorCode::MathOverflow)?;
+        }
+
+        // Escrowed tokens stay locked until voting ends so they cannot be reused
+        let voter = &mut ctx.accounts.voter;
+        voter.locked_until = voter.locked_until.max(proposal.voting_ends_at);
+
+        let vote_record = &mut ctx.accounts.vote_record;
+        vote_record.proposal = proposal.key();
+        vote_record.voter = ctx.accounts.owner.key();
+        vote_record.weight = weight;
+        vote_record.support = support;
+        vote_record.bump = ctx.bumps.vote_record;
+
+        emit!(VoteCastEvent {
+            proposal: proposal.key(),
+            voter: ctx.accounts.owner.key(),
+            support,
+            weight,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    pub fn execute_proposal(ctx: Context<ExecuteProposal>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let proposal = &mut ctx.accounts.proposal;
+
+        require!(!proposal.executed, ErrorCode::ProposalAlreadyExecuted);
+        require!(now >= proposal.eta, ErrorCode::ProposalNotReady);
+        require!(proposal.has_passed(), ErrorCode::ProposalNotPassed);
+
+        proposal.executed = true;
+
+        let governance = &mut ctx.accounts.governance;
+        governance.open_proposals = governance.open_proposals.saturating_sub(1);
+
+        let agent_state = &mut ctx.accounts.agent_state;
+        require!(agent_state.state != AgentStatus::Retired, ErrorCode::AgentRetired);
+
+        match proposal.action.clone() {
+            GovernanceAction::ChangePurpose { purpose } => {
+                let old_purpose = std::mem::replace(&mut agent_state.purpose, purpose.clone());
+                emit!(PurposeUpdatedEvent {
+                    agent: agent_state.key(),
+                    old_purpose,
+                    new_purpose: purpose,
+                    timestamp: now,
+                });
+            }
+            GovernanceAction::RotateAgentWallet { agent_wallet } => {
+                agent_state.agent_wallet = agent_wallet;
+            }
+            GovernanceAction::Pause => {
+                require!(
+                    agent_state.state == AgentStatus::Active,
+                    ErrorCode::AgentAlreadyPaused
+                );
+                agent_state.state = AgentStatus::Paused;
+                agent_state.paused_at = now;
+                agent_state.governance_paused = true;
+                emit!(AgentStatusChangedEvent {
+                    agent: agent_state.key(),
+                    new_status: AgentStatus::Paused,
+                    timestamp: now,
+                });
+            }
+            GovernanceAction::Resume => {
+                require!(
+                    agent_state.state == AgentStatus::Paused,
+                    ErrorCode::AgentAlreadyActive
+                );
+                agent_state.state = AgentStatus::Active;
+                agent_state.governance_paused = false;
+                emit!(AgentStatusChangedEvent {
+                    agent: agent_state.key(),
+                    new_status: AgentStatus::Active,
+                    timestamp: now,
+                });
+            }
+            GovernanceAction::UpdateRiskConfig { risk_config } => {
+                require!(!agent_state.is_subagent(), ErrorCode::InheritedRiskLimits);
+                agent_state.risk_config = risk_config;
+            }
+            GovernanceAction::Retire => {
+                agent_state.state = AgentStatus::Retired;
+                agent_state.retired_at = now;
+                emit!(AgentStatusChangedEvent {
+                    agent: agent_state.key(),
+                    new_status: AgentStatus::Retired,
+                    timestamp: now,
+                });
+            }
+        }
+
+        emit!(ProposalExecutedEvent {
+            agent: agent_state.key(),
+            proposal: proposal.key(),
+            timestamp: now,
+        });
+
+        msg!("Proposal {} executed", proposal.proposal_id);
+        Ok(())
+    }
+
+    /// Close a proposal that failed its vote, returning its rent to the proposer
+    pub fn close_proposal(ctx: Context<CloseProposal>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let proposal = &ctx.accounts.proposal;
+        require!(
+            now >= proposal.voting_ends_at && !proposal.has_passed(),
+            ErrorCode::ProposalActive
+        );
+
+        let governance = &mut ctx.accounts.governance;
+        governance.open_proposals = governance.open_proposals.saturating_sub(1);
+
+        emit!(ProposalClosedEvent {
+            agent: governance.agent,
+            proposal: proposal.key(),
+            timestamp: now,
+        });
+
+        msg!("Proposal {} closed", proposal.proposal_id);
+        Ok(())
+    }
+
+    pub fn queue_config_change(ctx: Context<QueueConfigChange>, change: ConfigChange) -> Result<()> {
+        change.validate()?;
+
+        // Sub-agents keep the risk limits inherited from their parent
+        require!(
+            !ctx.accounts.agent_state.is_subagent()
+                || !matches!(
+                    change,
+                    ConfigChange::RiskConfig { .. } | ConfigChange::TradingWhitelist { .. }
+                ),
+            ErrorCode::InheritedRiskLimits
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        let agent_state = &mut ctx.accounts.agent_state;
+        let pending = &mut ctx.accounts.pending_change;
+
+        pending.agent = agent_state.key();
+        pending.change_id = agent_state.config_change_count;
+        pending.change = change.clone();
+        pending.queued_at = now;
+        pending.eta = now.checked_add(agent_state.config_delay).ok_or(ErrorCode::MathOverflow)?;
+        pending.bump = ctx.bumps.pending_change;
+
+        agent_state.config_change_count += 1;
+        agent_state.pending_config_changes = agent_state
+            .pending_config_changes
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        emit!(ConfigChangeQueuedEvent {
+            agent: agent_state.key(),
+            pending_change: pending.key(),
+            change,
+            eta: pending.eta,
+            timestamp: now,
+        });
+
+        msg!("Config change {} queued, executable at {}", pending.change_id, pending.eta);
+        Ok(())
+    }
+
+    pub fn cancel_config_change(ctx: Context<CancelConfigChange>) -> Result<()> {
+        let agent_state = &mut ctx.accounts.agent_state;
+        agent_state.pending_config_changes = agent_state.pending_config_changes.saturating_sub(1);
+
+        emit!(ConfigChangeCancelledEvent {
+            agent: ctx.accounts.agent_state.key(),
+            pending_change: ctx.accounts.pending_change.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Config change cancelled");
+        Ok(())
+    }
+
+    pub fn execute_config_change(ctx: Context<ExecuteConfigChange>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(now >= ctx.accounts.pending_change.eta, ErrorCode::ConfigChangeNotReady);
+
+        let agent_state = &mut ctx.accounts.agent_state;
+        match ctx.accounts.pending_change.change.clone() {
+            ConfigChange::AgentWallet { agent_wallet } => agent_state.agent_wallet = agent_wallet,
+            ConfigChange::RiskConfig { risk_config } => agent_state.risk_config = risk_config,
+            ConfigChange::CopyFee { copy_fee_bps } => agent_state.copy_fee_bps = copy_fee_bps,
+            ConfigChange::TradingWhitelist { mints } => agent_state.trading_whitelist = mints,
+            ConfigChange::ConfigDelay { delay } => agent_state.config_delay = delay,
+            ConfigChange::EmergencyGracePeriod { grace_period } => {
+                agent_state.emergency_grace_period = grace_period
+            }
+            ConfigChange::RevenueMode { revenue_mode } => agent_state.revenue_mode = revenue_mode,
+        }
+        agent_state.pending_config_changes = agent_state.pending_config_changes.saturating_sub(1);
+
+        emit!(ConfigChangeExecutedEvent {
+            agent: agent_state.key(),
+            pending_change: ctx.accounts.pending_change.key(),
+            change: ctx.accounts.pending_change.change.clone(),
+            timestamp: now,
+        });
+
+        msg!("Config change {} executed", ctx.accounts.pending_change.change_id);
+        Ok(())
+    }
+
+    pub fn check_circuit_breaker(ctx: Context<CheckCircuitBreaker>) -> Result<()> {
+        let vault_balance = ctx.accounts.vault.lamports();
+        let agent_state = &mut ctx.accounts.agent_state;
+
+        let cause = agent_state
+            .circuit_breaker_cause(vault_balance)
+            .ok_or(ErrorCode::CircuitBreakerNotTriggered)?;
+        trip_circuit_breaker(agent_state, cause, Clock::get()?.unix_timestamp);
+
+        Ok(())
+    }
+
+    pub fn reset_circuit_breaker(ctx: Context<ResetCircuitBreaker>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let agent_state = &mut ctx.accounts.agent_state;
+
+        require!(
+            agent_state.state == AgentStatus::CircuitBroken,
+            ErrorCode::CircuitBreakerNotTripped
+        );
+        require!(
+            now >= agent_state
+                .circuit_broken_at
+                .saturating_add(agent_state.risk_config.breaker_cooldown),
+            ErrorCode::CooldownActive
+        );
+
+        agent_state.state = AgentStatus::Active;
+        agent_state.consecutive_losses = 0;
+        agent_state.daily_pnl = 0;
+        agent_state.day_started_at = now;
+        agent_state.day_start_balance = ctx.accounts.vault.lamports();
+
+        emit!(AgentStatusChangedEvent {
+            agent: agent_state.key(),
+            new_status: AgentStatus::Active,
+            timestamp: now,
+        });
+
+        msg!("Circuit breaker reset");
+        Ok(())
+    }
+
+    pub fn open_deposit_receipt(ctx: Context<OpenDepositReceipt>) -> Result<()> {
+        let receipt = &mut ctx.accounts.receipt;
+        receipt.agent = ctx.accounts.agent_state.key();
+        receipt.depositor = ctx.accounts.depositor.key();
+        receipt.amount = 0;
+        receipt.bump = ctx.bumps.receipt;
+
+        Ok(())
+    }
+
+    /// Depositors reclaim their pro-rata share of the vault principal, i.e. the
+    /// vault balance not owed to token holders through the revenue or buyback pools
+    pub fn emergency_withdraw(ctx: Context<EmergencyWithdraw>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let agent_state = &ctx.accounts.agent_state;
+        require!(agent_state.emergency_exit_open(now), ErrorCode::EmergencyExitClosed);
+        require!(!agent_state.has_debt, ErrorCode::DebtOutstanding);
+
+        let deposited = ctx.accounts.receipt.amount;
+        require!(deposited > 0 && agent_state.total_deposits > 0, ErrorCode::NothingToWithdraw);
+
+        let principal = agent_state.free_balance(ctx.accounts.vault.lamports());
+        let share = (principal as u128)
+            .checked_mul(deposited as u128)
+            .ok_or(ErrorCode::MathOverflow)?
+            .checked_div(agent_state.total_deposits as u128)
+            .ok_or(ErrorCode::MathOverflow)? as u64;
+        require!(share > 0, ErrorCode::ShareTooSmall);
+
+        transfer_from_vault(
+            agent_state.key(),
+            agent_state.vault_bump,
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.depositor.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            share,
+        )?;
+
+        let agent_state = &mut ctx.accounts.agent_state;
+        agent_state.total_deposits -= deposited;
+        agent_state.debit_vault(share);
+        ctx.accounts.receipt.amount = 0;
+
+        emit!(EmergencyWithdrawalEvent {
+            agent: agent_state.key(),
+            user: ctx.accounts.depositor.key(),
+            amount: share,
+            tokens_burned: 0,
+            timestamp: now,
+        });
+
+        msg!("Emergency withdrawal: {} lamports", share);
+        Ok(())
+    }
+
+    /// Holders burn agent tokens for their pro-rata share of the revenue pool
+    pub fn emergency_redeem(ctx: Context<EmergencyRedeem>, token_amount: u64) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let agent_state = &ctx.accounts.agent_state;
+        require!(agent_state.emergency_exit_open(now), ErrorCode::EmergencyExitClosed);
+        require!(!agent_state.has_debt, ErrorCode::DebtOutstanding);
+        require!(
+            token_amount > 0 && token_amount <= ctx.accounts.user_token_account.amount,
+            ErrorCode::InvalidAmount
+        );
+
+        let share = (agent_state.revenue_pool as u128)
+            .checked_mul(token_amount as u128)
+            .ok_or(ErrorCode::MathOverflow)?
+            .checked_div(ctx.accounts.token_mint.supply as u128)
+            .ok_or(ErrorCode::MathOverflow)? as u64;
+        require!(share > 0, ErrorCode::ShareTooSmall);
+
+        // Burning keeps later redemptions pro-rata against the reduced supply
+        token::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.token_mint.to_account_info(),
+                    from: ctx.accounts.user_token_account.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            ),
+            token_amount,
+        )?;
+
+        transfer_from_vault(
+            agent_state.key(),
+            agent_state.vault_bump,
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.user.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            share,
+        )?;
+
+        let agent_state = &mut ctx.accounts.agent_state;
+        agent_state.revenue_pool -= share;
+        agent_state.debit_vault(share);
+
+        emit!(EmergencyWithdrawalEvent {
+            agent: agent_state.key(),
+            user: ctx.accounts.user.key(),
+            amount: share,
+            tokens_burned: token_amount,
+            timestamp: now,
+        });
+
+        msg!("Emergency redemption: {} lamports for {} tokens", share, token_amount);
+        Ok(())
+    }
+
+    /// Spend the buyback pool on agent tokens sold by the keeper at the oracle
+    /// price and burn them
+    pub fn execute_buyback(ctx: Context<ExecuteBuyback>, max_lamports: u64) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let agent_state = &ctx.accounts.agent_state;
+
+        let lamports = agent_state.buyback_pool.min(max_lamports);
+        require!(lamports > 0, ErrorCode::NothingToBuyBack);
+        require!(!agent_state.has_debt, ErrorCode::DebtOutstanding);
+        require!(ctx.accounts.vault.lamports() >= lamports, ErrorCode::InsufficientFunds);
+
+        let price = fresh_price(&ctx.accounts.price_feed)?;
+        let tokens = quote_output(lamports, price, true)?;
+        require!(tokens > 0, ErrorCode::InvalidAmount);
+
+        token::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.token_mint.to_account_info(),
+                    from: ctx.accounts.keeper_token_account.to_account_info(),
+                    authority: ctx.accounts.keeper.to_account_info(),
+                },
+            ),
+            tokens,
+        )?;
+
+        transfer_from_vault(
+            agent_state.key(),
+            agent_state.vault_bump,
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.keeper.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            lamports,
+        )?;
+
+        let agent_state = &mut ctx.accounts.agent_state;
+        agent_state.buyback_pool -= lamports;
+        agent_state.debit_vault(lamports);
+        agent_state.total_burned = agent_state
+            .total_burned
+            .checked_add(tokens)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        emit!(BuybackExecutedEvent {
+            agent: agent_state.key(),
+            keeper: ctx.accounts.keeper.key(),
+            lamports_spent: lamports,
+            tokens_burned: tokens,
+            price,
+            timestamp: now,
+        });
+
+        msg!("Buyback burned {} tokens for {} lamports", tokens, lamports);
+        Ok(())
+    }
+
+    pub fn set_revenue_publisher(ctx: Context<SetRevenuePublisher>, publisher: Pubkey) -> Result<()> {
+        ctx.accounts.platform.revenue_publisher = publisher;
+
+        msg!("Revenue publisher set to: {}", publisher);
+        Ok(())
+    }
+
+    /// Move revenue pool SOL into a new epoch claimable against a merkle root
+    /// of holder entitlements built off-chain with the `revenue-merkle` crate
+    pub fn publish_revenue_epoch(
+        ctx: Context<PublishRevenueEpoch>,
+        merkle_root: [u8; 32],
+        total_amount: u64,
+        num_claimants: u32,
+        expires_at: i64,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(total_amount > 0, ErrorCode::InvalidAmount);
+        require!(
+            num_claimants > 0 && num_claimants <= MAX_EPOCH_CLAIMANTS,
+            ErrorCode::InvalidRevenueEpoch
+        );
+        require!(expires_at > now, ErrorCode::InvalidRevenueEpoch);
+
+        let agent_state = &ctx.accounts.agent_state;
+        require!(total_amount <= agent_state.revenue_pool, ErrorCode::NoRevenueAvailable);
+        require!(!agent_state.has_debt, ErrorCode::DebtOutstanding);
+
+        transfer_from_vault(
+            agent_state.key(),
+            agent_state.vault_bump,
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.epoch.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            total_amount,
+        )?;
+
+        let agent_state = &mut ctx.accounts.agent_state;
+        let epoch = &mut ctx.accounts.epoch;
+
+        epoch.agent = agent_state.key();
+        epoch.epoch_id = agent_state.revenue_epoch_count;
+        epoch.publisher = ctx.accounts.publisher.key();
+        epoch.merkle_root = merkle_root;
+        epoch.total_amount = total_amount;
+        epoch.claimed_amount = 0;
+        epoch.num_claimants = num_claimants;
+        epoch.published_at = now;
+        epoch.expires_at = expires_at;
+        epoch.bump = ctx.bumps.epoch;
+        epoch.claimed_bitmap = vec![0; RevenueEpoch::bitmap_len(num_claimants)];
+
+        agent_state.revenue_pool -= total_amount;
+        agent_state.debit_vault(total_amount);
+        agent_state.revenue_epoch_count += 1;
+        agent_state.open_revenue_epochs = agent_state
+            .open_revenue_epochs
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        emit!(RevenueEpochPublishedEvent {
+            agent: agent_state.key(),
+            epoch: epoch.key(),
+            epoch_id: epoch.epoch_id,
+            merkle_root,
+            total_amount,
+            num_claimants,
+            expires_at,
+            timestamp: now,
+        });
+
+        msg!("Revenue epoch {} published: {} lamports", epoch.epoch_id, total_amount);
+        Ok(())
+    }
+
+    pub fn claim_epoch_revenue(
+        ctx: Context<ClaimEpochRevenue>,
+        index: u32,
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let epoch = &mut ctx.accounts.epoch;
+
+        require!(now < epoch.expires_at, ErrorCode::RevenueEpochExpired);
+        require!(index < epoch.num_claimants, ErrorCode::InvalidProof);
+        require!(!epoch.is_claimed(index), ErrorCode::AlreadyClaimed);
+
+        let leaf = revenue_merkle::leaf_hash(index, &ctx.accounts.claimant.key(), amount);
+        require!(
+            revenue_merkle::verify(&proof, &epoch.merkle_root, leaf),
+            ErrorCode::InvalidProof
+        );
+
+        let claimed_amount = epoch
+            .claimed_amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(claimed_amount <= epoch.total_amount, ErrorCode::InsufficientFunds);
+
+        epoch.set_claimed(index);
+        epoch.claimed_amount = claimed_amount;
+
+        **epoch.to_account_info().try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.claimant.to_account_info().try_borrow_mut_lamports()? += amount;
+
+        emit!(RevenueClaimedEvent {
+            agent: epoch.agent,
+            user: ctx.accounts.claimant.key(),
+            amount,
+            timestamp: now,
+        });
+
+        msg!("Epoch revenue claimed: {} lamports", amount);
+        Ok(())
+    }
+
+    /// Close an expired epoch, rolling unclaimed SOL back into the revenue pool
+    pub fn close_revenue_epoch(ctx: Context<CloseRevenueEpoch>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let epoch = &ctx.accounts.epoch;
+        require!(now >= epoch.expires_at, ErrorCode::RevenueEpochActive);
+
+        let unclaimed = epoch.total_amount - epoch.claimed_amount;
+        let agent_state = &mut ctx.accounts.agent_state;
+        agent_state.open_revenue_epochs = agent_state.open_revenue_epochs.saturating_sub(1);
+
+        if unclaimed > 0 {
+            **ctx.accounts.epoch.to_account_info().try_borrow_mut_lamports()? -= unclaimed;
+            **ctx.accounts.vault.to_account_info().try_borrow_mut_lamports()? += unclaimed;
+
+            agent_state.revenue_pool = agent_state
+                .revenue_pool
+                .checked_add(unclaimed)
+                .ok_or(ErrorCode::MathOverflow)?;
+            agent_state.credit_vault(unclaimed)?;
+        }
+
+        emit!(RevenueEpochClosedEvent {
+            agent: ctx.accounts.agent_state.key(),
+            epoch: ctx.accounts.epoch.key(),
+            epoch_id: ctx.accounts.epoch.epoch_id,
+            rolled_over: unclaimed,
+            timestamp: now,
+        });
+
+        msg!("Revenue epoch closed, {} lamports rolled over", unclaimed);
+        Ok(())
+    }
+
+    /// Realloc a pre-versioning agent account to the current layout, with the
+    /// authority paying the extra rent
+    pub fn migrate_agent_state(ctx: Context<MigrateAgentState>) -> Result<()> {
+        let info = ctx.accounts.agent_state.to_account_info();
+        require!(
+            info.data_len() == 8 + AgentStateV0::INIT_SPACE,
+            ErrorCode::AlreadyMigrated
+        );
+
+        let legacy = {
+            let data = info.try_borrow_data()?;
+            require!(
+                data[..8] == AgentState::DISCRIMINATOR,
+                ErrorCode::InvalidMigrationAccount
+            );
+            AgentStateV0::deserialize(&mut &data[8..])?
+        };
+
+        require_keys_eq!(
+            legacy.authority,
+            ctx.accounts.authority.key(),
+            ErrorCode::InvalidMigrationAccount
+        );
+        let expected = Pubkey::create_program_address(
+            &[b"agent", legacy.authority.as_ref(), &[legacy.bump]],
+            ctx.program_id,
+        )
+        .map_err(|_| error!(ErrorCode::InvalidMigrationAccount))?;
+        require_keys_eq!(expected, info.key(), ErrorCode::InvalidMigrationAccount);
+
+        let new_space = 8 + AgentState::INIT_SPACE;
+        let rent_due = Rent::get()?
+            .minimum_balance(new_space)
+            .saturating_sub(info.lamports());
+        if rent_due > 0 {
+            let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
+                &ctx.accounts.authority.key(),
+                &info.key(),
+                rent_due,
+            );
+
+            anchor_lang::solana_program::program::invoke(
+                &transfer_ix,
+                &[
+                    ctx.accounts.authority.to_account_info(),
+                    info.clone(),
+                    ctx.accounts.system_program.to_account_info(),
+                ],
+            )?;
+        }
+
+        info.realloc(new_space, true)?;
+        let migrated = legacy.migrate();
+        migrated.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+
+        emit!(AccountMigratedEvent {
+            account: info.key(),
+            version: AGENT_STATE_VERSION,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Agent state migrated to version {}", AGENT_STATE_VERSION);
+        Ok(())
+    }
+
+    /// Create a child agent under `child_authority`, funded from the parent
+    /// vault and bound by the parent's risk limits
+    pub fn spawn_subagent(
+        ctx: Context<SpawnSubagent>,
+        name: String,
+        purpose: String,
+        agent_wallet: Pubkey,
+        budget: u64,
+    ) -> Result<()> {
+        require!(!name.is_empty() && name.len() <= 32, ErrorCode::InvalidName);
+        require!(!purpose.is_empty() && purpose.len() <= 200, ErrorCode::InvalidPurpose);
+        require!(agent_wallet != Pubkey::default(), ErrorCode::InvalidConfigChange);
+
+        let parent = &ctx.accounts.parent_state;
+        require!(parent.state == AgentStatus::Active, ErrorCode::AgentNotActive);
+        require!(!parent.is_subagent(), ErrorCode::NestedSubagent);
+        require!(!parent.has_debt, ErrorCode::DebtOutstanding);
+        require!(
+            budget > 0 && budget <= parent.free_balance(ctx.accounts.parent_vault.lamports()),
+            ErrorCode::InsufficientFunds
+        );
+
+        let child_state = AgentState {
+            risk_config: parent.risk_config.clone(),
+            trading_whitelist: parent.trading_whitelist.clone(),
+            config_delay: parent.config_delay,
+            emergency_grace_period: parent.emergency_grace_period,
+            revenue_mode: parent.revenue_mode.clone(),
+            parent: parent.key(),
+            subagent_budget: budget,
+            vault_balance: budget,
+            ..AgentState::new(
+                ctx.accounts.child_authority.key(),
+                agent_wallet,
+                parent.token_mint,
+                ctx.accounts.child_vault.key(),
+                name,
+                purpose,
+                ctx.bumps.child_state,
+                ctx.bumps.child_vault,
+            )
+        };
+        ctx.accounts.child_state.set_inner(child_state);
+
+        transfer_from_vault(
+            parent.key(),
+            parent.vault_bump,
+            &ctx.accounts.parent_vault.to_account_info(),
+            &ctx.accounts.child_vault.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            budget,
+        )?;
+
+        let parent = &mut ctx.accounts.parent_state;
+        parent.debit_vault(budget);
+        parent.subagent_count += 1;
+        parent.delegated_budget = parent
+            .delegated_budget
+            .checked_add(budget)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        emit!(SubagentSpawnedEvent {
+            parent: parent.key(),
+            child: ctx.accounts.child_state.key(),
+            child_authority: ctx.accounts.child_authority.key(),
+            budget,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Sub-agent spawned with budget {} lamports", budget);
+        Ok(())
+    }
+
+    /// Close a sub-agent and return its vault to the parent. Anything that
+    /// would be orphaned with the child's state must be settled first: loans
+    /// and pool supply, third-party deposits, orders, DCA schedules, followers,
+    /// royalties, revenue epochs, queued config changes and open proposals.
+    pub fn recall_subagent(ctx: Context<RecallSubagent>) -> Result<()> {
+        let child = &ctx.accounts.child_state;
+        require!(child.strategy_royalties == 0, ErrorCode::RoyaltiesOutstanding);
+        require!(!child.has_debt, ErrorCode::DebtOutstanding);
+        require!(child.total_deposits == 0, ErrorCode::SubagentBusy);
+        require!(
+            child.open_orders == 0 && child.open_dca_schedules == 0 && child.follower_count == 0,
+            ErrorCode::SubagentBusy
+        );
+        require!(
+            child.open_revenue_epochs == 0 && child.pending_config_changes == 0,
+            ErrorCode::SubagentBusy
+        );
+
+        // A lending position keyed by the child would be unreachable once it closes
+        let position_info = ctx.accounts.child_position.to_account_info();
+        if position_info.owner == &crate::ID {
+            let position = LendingPosition::try_deserialize(&mut &position_info.try_borrow_data()?[..])?;
+            require!(
+                position.debt_principal == 0 && position.supply_shares == 0,
+                ErrorCode::SubagentBusy
+            );
+        }
+
+        // Live proposals would execute against whatever agent next takes this address
+        let governance_info = ctx.accounts.child_governance.to_account_info();
+        if governance_info.owner == &crate::ID {
+            let governance =
+                GovernanceConfig::try_deserialize(&mut &governance_info.try_borrow_data()?[..])?;
+            require!(governance.open_proposals == 0, ErrorCode::SubagentBusy);
+        }
+
+        let returned = ctx.accounts.child_vault.lamports();
+
+        if returned > 0 {
+            transfer_from_vault(
+                child.key(),
+                child.vault_bump,
+                &ctx.accounts.child_vault.to_account_info(),
+                &ctx.accounts.parent_vault.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+                returned,
+            )?;
+        }
+
+        // Holder revenue accrued by the child now belongs to the parent's holders.
+        // Stray transfers into the child's vault stay untracked in the parent's.
+        let (revenue_pool, buyback_pool, budget) =
+            (child.revenue_pool, child.buyback_pool, child.subagent_budget);
+        let tracked = child.tracked_vault_balance(returned);
+
+        let parent = &mut ctx.accounts.parent_state;
+        parent.revenue_pool = parent
+            .revenue_pool
+            .checked_add(revenue_pool)
+            .ok_or(ErrorCode::MathOverflow)?;
+        parent.buyback_pool = parent
+            .buyback_pool
+            .checked_add(buyback_pool)
+            .ok_or(ErrorCode::MathOverflow)?;
+        parent.credit_vault(tracked)?;
+        parent.subagent_count = parent.subagent_count.saturating_sub(1);
+        parent.delegated_budget = parent.delegated_budget.saturating_sub(budget);
+
+        emit!(SubagentRecalledEvent {
+            parent: parent.key(),
+            child: ctx.accounts.child_state.key(),
+            returned,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Sub-agent recalled, {} lamports returned", returned);
+        Ok(())
+    }
+
+    pub fn list_strategy(
+        ctx: Context<ListStrategy>,
+        strategy_id: u64,
+        content_hash: [u8; 32],
+        price: u64,
+        license_model: LicenseModel,
+        royalty_bps: u16,
+    ) -> Result<()> {
+        require!(royalty_bps <= MAX_STRATEGY_ROYALTY_BPS, ErrorCode::InvalidRoyalty);
+        license_model.validate()?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let strategy = &mut ctx.accounts.strategy;
+        strategy.author = ctx.accounts.author.key();
+        strategy.strategy_id = strategy_id;
+        strategy.content_hash = content_hash;
+        strategy.price = price;
+        strategy.license_model = license_model;
+        strategy.royalty_bps = royalty_bps;
+        strategy.active = true;
+        strategy.license_count = 0;
+        strategy.proceeds = 0;
+        strategy.total_sales = 0;
+        strategy.total_royalties = 0;
+        strategy.created_at = now;
+        strategy.bump = ctx.bumps.strategy;
+
+        emit!(StrategyListedEvent {
+            strategy: strategy.key(),
+            author: strategy.author,
+            content_hash,
+            price,
+            royalty_bps,
+            timestamp: now,
+        });
+
+        msg!("Strategy listed at {} lamports, {} bps royalty", price, royalty_bps);
+        Ok(())
+    }
+
+    /// Change the terms offered to new licensees; existing licenses keep theirs
+    pub fn update_strategy(
+        ctx: Context<UpdateStrategy>,
+        content_hash: [u8; 32],
+        price: u64,
+        royalty_bps: u16,
+        active: bool,
+    ) -> Result<()> {
+        require!(royalty_bps <= MAX_STRATEGY_ROYALTY_BPS, ErrorCode::InvalidRoyalty);
+
+        let strategy = &mut ctx.accounts.strategy;
+        strategy.content_hash = content_hash;
+        strategy.price = price;
+        strategy.royalty_bps = royalty_bps;
+        strategy.active = active;
+
+        msg!("Strategy updated: price {}, active {}", price, active);
+        Ok(())
+    }
+
+    /// Buy a license for an agent, paying the price into the strategy's escrow
+    pub fn purchase_license(ctx: Context<PurchaseLicense>) -> Result<()> {
+        let strategy = &ctx.accounts.strategy;
+        require!(strategy.active, ErrorCode::StrategyInactive);
+
+        let now = Clock::get()?.unix_timestamp;
+        let price = strategy.price;
+        pay_strategy(
+            &ctx.accounts.authority.to_account_info(),
+            &ctx.accounts.strategy.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            price,
+        )?;
+
+        let license = &mut ctx.accounts.license;
+        license.strategy = ctx.accounts.strategy.key();
+        license.agent = ctx.accounts.agent_state.key();
+        license.royalty_bps = ctx.accounts.strategy.royalty_bps;
+        license.purchased_at = now;
+        license.expires_at = ctx.accounts.strategy.license_model.expiry(now, now);
+        license.bump = ctx.bumps.license;
+
+        let strategy = &mut ctx.accounts.strategy;
+        strategy.license_count += 1;
+        strategy.proceeds = strategy.proceeds.checked_add(price).ok_or(ErrorCode::MathOverflow)?;
+        strategy.total_sales = strategy
+            .total_sales
+            .checked_add(price)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        emit!(LicensePurchasedEvent {
+            strategy: strategy.key(),
+            agent: license.agent,
+            price,
+            expires_at: license.expires_at,
+            timestamp: now,
+        });
+
+        msg!("Strategy license purchased for {} lamports", price);
+        Ok(())
+    }
+
+    /// Extend a subscription license by another period at the current price
+    pub fn renew_license(ctx: Context<RenewLicense>) -> Result<()> {
+        let strategy = &ctx.accounts.strategy;
+        require!(strategy.active, ErrorCode::StrategyInactive);
+        require!(ctx.accounts.license.expires_at != 0, ErrorCode::PerpetualLicense);
+
+        let now = Clock::get()?.unix_timestamp;
+        let price = strategy.price;
+        pay_strategy(
+            &ctx.accounts.authority.to_account_info(),
+            &ctx.accounts.strategy.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            price,
+        )?;
+
+        let license = &mut ctx.accounts.license;
+        license.expires_at = ctx.accounts.strategy.license_model.expiry(now, license.expires_at);
+
+        let agent_state = &mut ctx.accounts.agent_state;
+        if agent_state.strategy == license.strategy {
+            agent_state.strategy_expires_at = license.expires_at;
+        }
+
+        let strategy = &mut ctx.accounts.strategy;
+        strategy.proceeds = strategy.proceeds.checked_add(price).ok_or(ErrorCode::MathOverflow)?;
+        strategy.total_sales = strategy
+            .total_sales
+            .checked_add(price)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        emit!(LicensePurchasedEvent {
+            strategy: strategy.key(),
+            agent: license.agent,
+            price,
+            expires_at: license.expires_at,
+            timestamp: now,
+        });
+
+        msg!("Strategy license renewed until {}", license.expires_at);
+        Ok(())
+    }
+
+    /// Record the licensed strategy the agent runs. Royalties owed under the
+    /// previous strategy must be paid before switching.
+    pub fn adopt_strategy(ctx: Context<AdoptStrategy>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let license = &ctx.accounts.license;
+        require!(
+            license.expires_at == 0 || now < license.expires_at,
+            ErrorCode::LicenseExpired
+        );
+
+        let agent_state = &mut ctx.accounts.agent_state;
+        require!(agent_state.strategy_royalties == 0, ErrorCode::RoyaltiesOutstanding);
+
+        agent_state.strategy = license.strategy;
+        agent_state.strategy_royalty_bps = license.royalty_bps;
+        agent_state.strategy_expires_at = license.expires_at;
+
+        emit!(StrategyAdoptedEvent {
+            agent: agent_state.key(),
+            strategy: license.strategy,
+            royalty_bps: license.royalty_bps,
+            timestamp: now,
+        });
+
+        msg!("Agent now runs strategy {}", license.strategy);
+        Ok(())
+    }
+
+    /// Move royalties accrued in the vault into the strategy's escrow.
+    /// Permissionless so authors are paid without the agent's cooperation.
+    pub fn pay_strategy_royalties(ctx: Context<PayStrategyRoyalties>) -> Result<()> {
+        let amount = ctx.accounts.agent_state.strategy_royalties;
+        require!(amount > 0, ErrorCode::NoRoyaltiesOwed);
+        require!(!ctx.accounts.agent_state.has_debt, ErrorCode::DebtOutstanding);
+        require!(ctx.accounts.vault.lamports() >= amount, ErrorCode::InsufficientFunds);
+
+        let agent_state = &mut ctx.accounts.agent_state;
+        transfer_from_vault(
+            agent_state.key(),
+            agent_state.vault_bump,
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.strategy.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            amount,
+        )?;
+        agent_state.strategy_royalties = 0;
+        agent_state.debit_vault(amount);
+
+        let strategy = &mut ctx.accounts.strategy;
+        strategy.proceeds = strategy.proceeds.checked_add(amount).ok_or(ErrorCode::MathOverflow)?;
+        strategy.total_royalties = strategy
+            .total_royalties
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        emit!(StrategyRoyaltiesPaidEvent {
+            strategy: strategy.key(),
+            agent: agent_state.key(),
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        msg!("Strategy royalties paid: {} lamports", amount);
+        Ok(())
+    }
+
+    /// Withdraw license sales and royalties held in the strategy's escrow
+    pub fn withdraw_strategy_proceeds(ctx: Context<WithdrawStrategyProceeds>) -> Result<()> {
+        let amount = ctx.accounts.strategy.proceeds;
+        require!(amount > 0, ErrorCode::NothingToWithdraw);
+
+        let strategy_info = ctx.accounts.strategy.to_account_info();
+        let author_info = ctx.accounts.author.to_account_info();
+        **strategy_info.try_borrow_mut_lamports()? -= amount;
+        **author_info.try_borrow_mut_lamports()? += amount;
+
+        ctx.accounts.strategy.proceeds = 0;
+
+        msg!("Strategy proceeds withdrawn: {} lamports", amount);
+        Ok(())
+    }
+}
+
+/// Transfer lamports out of a system-owned PDA, signing with its seeds
+fn transfer_signed<'info>(
+    from: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    amount: u64,
+    seeds: &[&[u8]],
+) -> Result<()> {
+    let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
+        &from.key(),
+        &to.key(),
+        amount,
+    );
+
+    anchor_lang::solana_program::program::invoke_signed(
+        &transfer_ix,
+        &[from.clone(), to.clone(), system_program.clone()],
+        &[seeds],
+    )?;
+
+    Ok(())
+}
+
+/// Transfer lamports out of an agent's vault PDA, signing with the vault seeds
+fn transfer_from_vault<'info>(
+    agent_key: Pubkey,
+    vault_bump: u8,
+    vault: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    transfer_signed(
+        vault,
+        to,
+        system_program,
+        amount,
+        &[b"vault", agent_key.as_ref(), &[vault_bump]],
+    )
+}
+
+/// Pay a strategy license price from the buyer into the strategy's escrow
+fn pay_strategy<'info>(
+    buyer: &AccountInfo<'info>,
+    strategy: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    price: u64,
+) -> Result<()> {
+    if price == 0 {
+        return Ok(());
+    }
+
+    anchor_lang::system_program::transfer(
+        CpiContext::new(
+            system_program.clone(),
+            anchor_lang::system_program::Transfer {
+                from: buyer.clone(),
+                to: strategy.clone(),
+            },
+        ),
+        price,
+    )
+}
+
+/// Book a trade against the agent: collect the platform fee on profit, add the
+/// remainder to the revenue pool and update trading stats
+#[allow(clippy::too_many_arguments)]
+fn settle_trade<'info>(
+    agent_state: &mut Account<'info, AgentState>,
+    vault: &AccountInfo<'info>,
+    treasury: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    from_mint: Pubkey,
+    to_mint: Pubkey,
+    amount: u64,
+    actual_output: u64,
+) -> Result<()> {
+    let vault_balance = vault.lamports();
+    agent_state.risk_config.check_trade(amount, vault_balance)?;
+    require!(
+        agent_state.can_trade(&from_mint) && agent_state.can_trade(&to_mint),
+        ErrorCode::MintNotWhitelisted
+    );
+
+    let timestamp = Clock::get()?.unix_timestamp;
+
+    // Calculate profit (if any)
+    if actual_output > amount {
+        let profit = actual_output.checked_sub(amount).ok_or(ErrorCode::MathOverflow)?;
+
+        // 1% platform fee from profit
+        let platform_fee = profit.checked_div(100).unwrap_or(0);
+
+        // Remaining 99% goes to revenue pool for token holders
+        let mut revenue_for_holders = profit
+            .checked_sub(platform_fee)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        // The licensed strategy's author takes its royalty first
+        if agent_state.strategy_license_active(timestamp) {
+            let royalty = bps_of(revenue_for_holders, agent_state.strategy_royalty_bps);
+            agent_state.strategy_royalties = agent_state
+                .strategy_royalties
+                .checked_add(royalty)
+                .ok_or(ErrorCode::MathOverflow)?;
+            revenue_for_holders -= royalty;
+
+            if royalty > 0 {
+                msg!("Strategy royalty accrued: {} lamports", royalty);
+            }
+        }
+
+        // Transfer platform fee to treasury
+        if platform_fee > 0 {
+            transfer_from_vault(
+                agent_state.key(),
+                agent_state.vault_bump,
+                vault,
+                treasury,
+                system_program,
+                platform_fee,
+            )?;
+            agent_state.debit_vault(platform_fee);
+
+            msg!("Platform fee collected: {} lamports", platform_fee);
+        }
+
+        // Route holder revenue to distribution or to buyback per the revenue mode
+        let for_buyback = agent_state.revenue_mode.buyback_share(revenue_for_holders);
+        let for_distribution = revenue_for_holders - for_buyback;
+
+        agent_state.revenue_pool = agent_state
+            .revenue_pool
+            .checked_add(for_distribution)
+            .ok_or(ErrorCode::MathOverflow)?;
+        agent_state.buyback_pool = agent_state
+            .buyback_pool
+            .checked_add(for_buyback)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        msg!("Revenue added to pool: {} lamports", for_distribution);
+        if for_buyback > 0 {
+            msg!("Revenue set aside for buyback: {} lamports", for_buyback);
+        }
+    }
+
+    agent_state.total_trades += 1;
+    agent_state.total_volume = agent_state
+        .total_volume
+        .checked_add(amount)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    agent_state.last_trade = TradeSnapshot {
+        trade_id: agent_state.total_trades,
+        from_mint,
+        to_mint,
+        amount_in: amount,
+        amount_out: actual_output,
+        vault_balance,
+        timestamp,
+    };
+    agent_state.record_trade_result(amount, actual_output, vault_balance, timestamp);
+
+    emit!(TradeExecutedEvent {
+        agent: agent_state.key(),
+        from_mint,
+        to_mint,
+        amount_in: amount,
+        amount_out: actual_output,
+        timestamp,
+    });
+
+    msg!("Trade executed: {} -> {}", from_mint, to_mint);
+    msg!("Amount: {}, Output: {}", amount, actual_output);
+
+    if let Some(cause) = agent_state.circuit_breaker_cause(vault.lamports()) {
+        trip_circuit_breaker(agent_state, cause, timestamp);
+    }
+
+    Ok(())
+}
+
+/// Book a fill priced by the oracle. Both legs are worth `value` lamports, so
+/// unlike `settle_trade` no profit, platform fee or holder revenue is booked;
+/// the trade counts towards stats, risk limits and copy trading only.
+fn record_fill<'info>(
+    agent_state: &mut Account<'info, AgentState>,
+    vault: &AccountInfo<'info>,
+    from_mint: Pubkey,
+    to_mint: Pubkey,
+    value: u64,
+) -> Result<()> {
+    let vault_balance = vault.lamports();
+    agent_state.risk_config.check_trade(value, vault_balance)?;
+    require!(
+        agent_state.can_trade(&from_mint) && agent_state.can_trade(&to_mint),
+        ErrorCode::MintNotWhitelisted
+    );
+
+    let timestamp = Clock::get()?.unix_timestamp;
+    agent_state.total_trades += 1;
+    agent_state.total_volume = agent_state
+        .total_volume
+        .checked_add(value)
+        .ok_or(ErrorCode::MathOverflow)?;
+    agent_state.last_trade = TradeSnapshot {
+        trade_id: agent_state.total_trades,
+        from_mint,
+        to_mint,
+        amount_in: value,
+        amount_out: value,
+        vault_balance,
+        timestamp,
+    };
+
+    emit!(TradeExecutedEvent {
+        agent: agent_state.key(),
+        from_mint,
+        to_mint,
+        amount_in: value,
+        amount_out: value,
+        timestamp,
+    });
+
+    Ok(())
+}
+
+/// Halt an agent whose risk thresholds were breached
+fn trip_circuit_breaker(agent_state: &mut Account<AgentState>, cause: CircuitBreakerCause, now: i64) {
+    agent_state.state = AgentStatus::CircuitBroken;
+    agent_state.circuit_broken_at = now;
+
+    emit!(CircuitBreakerTrippedEvent {
+        agent: agent_state.key(),
+        cause,
+        consecutive_losses: agent_state.consecutive_losses,
+        daily_pnl: agent_state.daily_pnl,
+        timestamp: now,
+    });
+
+    emit!(AgentStatusChangedEvent {
+        agent: agent_state.key(),
+        new_status: AgentStatus::CircuitBroken,
+        timestamp: now,
+    });
+
+    msg!("Circuit breaker tripped");
+}
+
+/// Convert an input amount at a `PRICE_SCALE` price: lamports to tokens when
+/// buying, tokens to lamports when selling
+fn quote_output(amount: u64, price: u64, buying: bool) -> Result<u64> {
+    let (numerator, denominator) = if buying {
+        (PRICE_SCALE, price)
+    } else {
+        (price, PRICE_SCALE)
+    };
+
+    let output = (amount as u128)
+        .checked_mul(numerator as u128)
+        .ok_or(ErrorCode::MathOverflow)?
+        .checked_div(denominator as u128)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    u64::try_from(output).map_err(|_| error!(ErrorCode::MathOverflow))
+}
+
+/// Apply a basis point ratio to an amount, rounding down
+fn bps_of(amount: u64, bps: u16) -> u64 {
+    ((amount as u128) * (bps as u128) / (MAX_BPS as u128)) as u64
+}
+
+/// Compute `amount * numerator / denominator`, rounding down
+fn mul_div(amount: u64, numerator: u64, denominator: u64) -> Result<u64> {
+    let result = (amount as u128)
+        .checked_mul(numerator as u128)
+        .ok_or(ErrorCode::MathOverflow)?
+        .checked_div(denominator as u128)
+        .ok_or(ErrorCode::MathOverflow)?;
+
+    u64::try_from(result).map_err(|_| error!(ErrorCode::MathOverflow))
+}
+
+/// Read a price feed, rejecting quotes older than `MAX_PRICE_AGE`
+fn fresh_price(price_feed: &PriceFeed) -> Result<u64> {
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        now.saturating_sub(price_feed.updated_at) <= MAX_PRICE_AGE,
+        ErrorCode::StalePrice
+    );
+    require!(price_feed.price > 0, ErrorCode::InvalidPrice);
+    Ok(price_feed.price)
+}
+
+#[derive(Accounts)]
+#[instruction(name: String, purpose: String)]
+pub struct InitializeAgent<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + AgentState::INIT_SPACE,
+        seeds = [b"agent", authority.key().as_ref()],
+        bump
+    )]
+    pub agent_state: Account<'info, AgentState>,
+
+    #[account(
+        seeds = [b"vault", agent_state.key().as_ref()],
+        bump
+    )]
+    /// CHECK: PDA vault for agent funds
+    pub vault: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositFunds<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", agent_state.authority.as_ref()],
+        bump = agent_state.bump
+    )]
+    pub agent_state: Account<'info, AgentState>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", agent_state.key().as_ref()],
+        bump = agent_state.vault_bump
+    )]
+    /// CHECK: PDA vault
+    pub vault: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"deposit", agent_state.key().as_ref(), depositor.key().as_ref()],
+        bump = receipt.bump
+    )]
+    pub receipt: Account<'info, DepositReceipt>,
+
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteTrade<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", agent_state.authority.as_ref()],
+        bump = agent_state.bump
+    )]
+    pub agent_state: Account<'info, AgentState>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", agent_state.key().as_ref()],
+        bump = agent_state.vault_bump
+    )]
+    /// CHECK: PDA vault
+    pub vault: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    /// CHECK: AGENT.FUN treasury wallet - validated in backend
+    pub treasury: UncheckedAccount<'info>,
+
+    pub signer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRevenue<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", agent_state.authority.as_ref()],
+        bump = agent_state.bump
+    )]
+    pub agent_state: Account<'info, AgentState>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", agent_state.key().as_ref()],
+        bump = agent_state.vault_bump
+    )]
+    /// CHECK: PDA vault
+    pub vault: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        associated_token::mint = token_mint,
+        associated_token::authority = user
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+
+    #[account(address = agent_state.token_mint)]
+    pub token_mint: Account<'info, anchor_spl::token::Mint>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePurpose<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", agent_state.authority.as_ref()],
+        bump = agent_state.bump,
+        has_one = authority
+    )]
+    pub agent_state: Account<'info, AgentState>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct PauseAgent<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", agent_state.authority.as_ref()],
+        bump = agent_state.bump,
+        has_one = authority
+    )]
+    pub agent_state: Account<'info, AgentState>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ResumeAgent<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", agent_state.authority.as_ref()],
+        bump = agent_state.bump,
+        has_one = authority
+    )]
+    pub agent_state: Account<'info, AgentState>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializePlatform<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + PlatformConfig::INIT_SPACE,
+        seeds = [b"platform"],
+        bump
+    )]
+    pub platform: Account<'info, PlatformConfig>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::AgentManager>,
+
+    /// Only the upgrade authority can create the platform and become its admin
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(admin.key())
+            @ ErrorCode::UnauthorizedAdmin
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(token_mint: Pubkey)]
+pub struct InitializePriceFeed<'info> {
+    #[account(
+        seeds = [b"platform"],
+        bump = platform.bump,
+        has_one = admin
+    )]
+    pub platform: Account<'info, PlatformConfig>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + PriceFeed::INIT_SPACE,
+        seeds = [b"price_feed", token_mint.as_ref()],
+        bump
+    )]
+    pub price_feed: Account<'info, PriceFeed>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePrice<'info> {
+    #[account(
+        mut,
+        seeds = [b"price_feed", price_feed.token_mint.as_ref()],
+        bump = price_feed.bump,
+        has_one = oracle
+    )]
+    pub price_feed: Account<'info, PriceFeed>,
+
+    pub oracle: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct PlaceOrder<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", agent_state.authority.as_ref()],
+        bump = agent_state.bump,
+        has_one = authority
+    )]
+    pub agent_state: Account<'info, AgentState>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Order::INIT_SPACE,
+        seeds = [b"order", agent_state.key().as_ref(), &agent_state.order_count.to_le_bytes()],
+        bump
+    )]
+    pub order: Account<'info, Order>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelOrder<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", agent_state.authority.as_ref()],
+        bump = agent_state.bump,
+        has_one = authority
+    )]
+    pub agent_state: Account<'info, AgentState>,
+
+    #[account(
+        mut,
+        seeds = [b"order", agent_state.key().as_ref(), &order.order_id.to_le_bytes()],
+        bump = order.bump,
+        constraint = order.agent == agent_state.key() @ ErrorCode::InvalidOrder,
+        close = authority
+    )]
+    pub order: Account<'info, Order>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteOrder<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", agent_state.authority.as_ref()],
+        bump = agent_state.bump
+    )]
+    pub agent_state: Account<'info, AgentState>,
+
+    #[account(
+        mut,
+        seeds = [b"order", agent_state.key().as_ref(), &order.order_id.to_le_bytes()],
+        bump = order.bump,
+        constraint = order.agent == agent_state.key() @ ErrorCode::InvalidOrder,
+        close = authority
+    )]
+    pub order: Account<'info, Order>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", agent_state.key().as_ref()],
+        bump = agent_state.vault_bump
+    )]
+    /// CHECK: PDA vault
+    pub vault: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"price_feed", order.token_mint.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Account<'info, PriceFeed>,
+
+    #[account(mut, address = agent_state.authority)]
+    /// CHECK: Agent authority, receives the order rent on close
+    pub authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateDcaSchedule<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", agent_state.authority.as_ref()],
+        bump = agent_state.bump,
+        has_one = authority
+    )]
+    pub agent_state: Account<'info, AgentState>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + DcaSchedule::INIT_SPACE,
+        seeds = [b"dca", agent_state.key().as_ref(), &agent_state.dca_count.to_le_bytes()],
+        bump
+    )]
+    pub schedule: Account<'info, DcaSchedule>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelDcaSchedule<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", agent_state.authority.as_ref()],
+        bump = agent_state.bump,
+        has_one = authority
+    )]
+    pub agent_state: Account<'info, AgentState>,
+
+    #[account(
+        mut,
+        seeds = [b"dca", agent_state.key().as_ref(), &schedule.schedule_id.to_le_bytes()],
+        bump = schedule.bump,
+        constraint = schedule.agent == agent_state.key() @ ErrorCode::InvalidDcaSchedule,
+        close = authority
+    )]
+    pub schedule: Account<'info, DcaSchedule>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CrankDca<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", agent_state.authority.as_ref()],
+        bump = agent_state.bump
+    )]
+    pub agent_state: Account<'info, AgentState>,
+
+    #[account(
+        mut,
+        seeds = [b"dca", agent_state.key().as_ref(), &schedule.schedule_id.to_le_bytes()],
+        bump = schedule.bump,
+        constraint = schedule.agent == agent_state.key() @ ErrorCode::InvalidDcaSchedule
+    )]
+    pub schedule: Account<'info, DcaSchedule>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", agent_state.key().as_ref()],
+        bump = agent_state.vault_bump
+    )]
+    /// CHECK: PDA vault
+    pub vault: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"price_feed", price_feed.token_mint.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Account<'info, PriceFeed>,
+
+    #[account(mut, address = agent_state.authority)]
+    /// CHECK: Agent authority, receives the schedule rent on completion
+    pub authority: UncheckedAccount<'info>,
+
+    pub keeper: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FollowAgent<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", leader_state.authority.as_ref()],
+        bump = leader_state.bump
+    )]
+    pub leader_state: Account<'info, AgentState>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + FollowerState::INIT_SPACE,
+        seeds = [b"follower", leader_state.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub follower_state: Account<'info, FollowerState>,
+
+    #[account(
+        mut,
+        seeds = [b"follower_vault", follower_state.key().as_ref()],
+        bump
+    )]
+    /// CHECK: PDA vault for follower funds
+    pub follower_vault: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MirrorTrade<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", leader_state.authority.as_ref()],
+        bump = leader_state.bump
+    )]
+    pub leader_state: Account<'info, AgentState>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", leader_state.key().as_ref()],
+        bump = leader_state.vault_bump
+    )]
+    /// CHECK: Leader PDA vault, receives the follower fee
+    pub leader_vault: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"follower", leader_state.key().as_ref(), follower_state.owner.as_ref()],
+        bump = follower_state.bump
+    )]
+    pub follower_state: Account<'info, FollowerState>,
+
+    #[account(
+        mut,
+        seeds = [b"follower_vault", follower_state.key().as_ref()],
+        bump = follower_state.vault_bump
+    )]
+    /// CHECK: PDA vault for follower funds
+    pub follower_vault: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"price_feed", price_feed.token_mint.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Account<'info, PriceFeed>,
+
+    pub keeper: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ResyncFollower<'info> {
+    #[account(
+        seeds = [b"agent", leader_state.authority.as_ref()],
+        bump = leader_state.bump
+    )]
+    pub leader_state: Account<'info, AgentState>,
+
+    #[account(
+        mut,
+        seeds = [b"follower", leader_state.key().as_ref(), owner.key().as_ref()],
+        bump = follower_state.bump,
+        has_one = owner
+    )]
+    pub follower_state: Account<'info, FollowerState>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UnfollowAgent<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", leader_state.authority.as_ref()],
+        bump = leader_state.bump
+    )]
+    pub leader_state: Account<'info, AgentState>,
+
+    #[account(
+        mut,
+        seeds = [b"follower", leader_state.key().as_ref(), owner.key().as_ref()],
+        bump = follower_state.bump,
+        has_one = owner,
+        close = owner
+    )]
+    pub follower_state: Account<'info, FollowerState>,
+
+    #[account(
+        mut,
+        seeds = [b"follower_vault", follower_state.key().as_ref()],
+        bump = follower_state.vault_bump
+    )]
+    /// CHECK: PDA vault for follower funds
+    pub follower_vault: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeLendingPool<'info> {
+    #[account(
+        seeds = [b"platform"],
+        bump = platform.bump,
+        has_one = admin
+    )]
+    pub platform: Account<'info, PlatformConfig>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + LendingPool::INIT_SPACE,
+        seeds = [b"lending_pool"],
+        bump
+    )]
+    pub pool: Account<'info, LendingPool>,
+
+    #[account(
+        seeds = [b"pool_vault"],
+        bump
+    )]
+    /// CHECK: PDA vault holding pool liquidity
+    pub pool_vault: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenLendingPosition<'info> {
+    #[account(
+        seeds = [b"agent", agent_state.authority.as_ref()],
+        bump = agent_state.bump,
+        has_one = authority
+    )]
+    pub agent_state: Account<'info, AgentState>,
+
+    #[account(
+        seeds = [b"lending_pool"],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, LendingPool>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + LendingPosition::INIT_SPACE,
+        seeds = [b"lending_position", agent_state.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, LendingPosition>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AgentLending<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", agent_state.authority.as_ref()],
+        bump = agent_state.bump,
+        has_one = authority
+    )]
+    pub agent_state: Account<'info, AgentState>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", agent_state.key().as_ref()],
+        bump = agent_state.vault_bump
+    )]
+    /// CHECK: PDA vault
+    pub vault: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"lending_pool"],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, LendingPool>,
+
+    #[account(
+        mut,
+        seeds = [b"pool_vault"],
+        bump = pool.vault_bump
+    )]
+    /// CHECK: PDA vault holding pool liquidity
+    pub pool_vault: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"lending_position", agent_state.key().as_ref()],
+        bump = position.bump
+    )]
+    pub position: Account<'info, LendingPosition>,
+
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Liquidate<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", agent_state.authority.as_ref()],
+        bump = agent_state.bump
+    )]
+    pub agent_state: Account<'info, AgentState>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", agent_state.key().as_ref()],
+        bump = agent_state.vault_bump
+    )]
+    /// CHECK: PDA vault
+    pub vault: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"lending_pool"],
+        bump = pool.bump
+    )]
+    pub pool: Account<'info, LendingPool>,
+
+    #[account(
+        mut,
+        seeds = [b"pool_vault"],
+        bump = pool.vault_bump
+    )]
+    /// CHECK: PDA vault holding pool liquidity
+    pub pool_vault: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"lending_position", agent_state.key().as_ref()],
+        bump = position.bump
+    )]
+    pub position: Account<'info, LendingPosition>,
+
+    #[account(mut)]
+    pub liquidator: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeGovernance<'info> {
+    #[account(
+        seeds = [b"agent", agent_state.authority.as_ref()],
+        bump = agent_state.bump,
+        has_one = authority
+    )]
+    pub agent_state: Account<'info, AgentState>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + GovernanceConfig::INIT_SPACE,
+        seeds = [b"governance", agent_state.key().as_ref()],
+        bump
+    )]
+    pub governance: Account<'info, GovernanceConfig>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateVoterEscrow<'info> {
+    #[account(
+        seeds = [b"agent", agent_state.authority.as_ref()],
+        bump = agent_state.bump
+    )]
+    pub agent_state: Account<'info, AgentState>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + VoterEscrow::INIT_SPACE,
+        seeds = [b"voter", agent_state.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub voter: Account<'info, VoterEscrow>,
+
+    #[account(
+        init,
+        payer = owner,
+        seeds = [b"voter_tokens", voter.key().as_ref()],
+        bump,
+        token::mint = token_mint,
+        token::authority = voter
+    )]
+    pub escrow_tokens: Account<'info, TokenAccount>,
+
+    #[account(address = agent_state.token_mint)]
+    pub token_mint: Account<'info, anchor_spl::token::Mint>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct DepositVotingTokens<'info> {
+    #[account(
+        mut,
+        seeds = [b"voter", voter.agent.as_ref(), owner.key().as_ref()],
+        bump = voter.bump,
+        has_one = owner
+    )]
+    pub voter: Account<'info, VoterEscrow>,
+
+    #[account(
+        mut,
+        seeds = [b"voter_tokens", voter.key().as_ref()],
+        bump
+    )]
+    pub escrow_tokens: Account<'info, TokenAccount>,
+
+    #[account(mut, token::authority = owner)]
+    pub owner_token_account: Account<'info, TokenAccount>,
+
+    pub owner: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawVotingTokens<'info> {
+    #[account(
+        mut,
+        seeds = [b"voter", voter.agent.as_ref(), owner.key().as_ref()],
+        bump = voter.bump,
+        has_one = owner
+    )]
+    pub voter: Account<'info, VoterEscrow>,
+
+    #[account(
+        mut,
+        seeds = [b"voter_tokens", voter.key().as_ref()],
+        bump
+    )]
+    pub escrow_tokens: Account<'info, TokenAccount>,
+
+    #[account(mut, token::mint = escrow_tokens.mint)]
+    pub owner_token_account: Account<'info, TokenAccount>,
+
+    pub owner: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CreateProposal<'info> {
+    #[account(
+        mut,
+        seeds = [b"governance", governance.agent.as_ref()],
+        bump = governance.bump
+    )]
+    pub governance: Account<'info, GovernanceConfig>,
+
+    #[account(
+        seeds = [b"voter", governance.agent.as_ref(), proposer.key().as_ref()],
+        bump = voter.bump
+    )]
+    pub voter: Account<'info, VoterEscrow>,
+
+    #[account(
+        init,
+        payer = proposer,
+        space = 8 + Proposal::INIT_SPACE,
+        seeds = [b"proposal", governance.agent.as_ref(), &governance.proposal_count.to_le_bytes()],
+        bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        seeds = [b"agent", agent_state.authority.as_ref()],
+        bump = agent_state.bump,
+        constraint = agent_state.key() == governance.agent @ ErrorCode::InvalidProposal
+    )]
+    pub agent_state: Account<'info, AgentState>,
+
+    #[account(address = agent_state.token_mint)]
+    pub token_mint: Account<'info, anchor_spl::token::Mint>,
+
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CastVote<'info> {
+    #[account(
+        mut,
+        seeds = [b"proposal", proposal.agent.as_ref(), &proposal.proposal_id.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        mut,
+        seeds = [b"voter", proposal.agent.as_ref(), owner.key().as_ref()],
+        bump = voter.bump,
+        has_one = owner
+    )]
+    pub voter: Account<'info, VoterEscrow>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + VoteRecord::INIT_SPACE,
+        seeds = [b"vote", proposal.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub vote_record: Account<'info, VoteRecord>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteProposal<'info> {
+    #[account(
+        mut,
+        seeds = [b"proposal", agent_state.key().as_ref(), &proposal.proposal_id.to_le_bytes()],
+        bump = proposal.bump,
+        constraint = proposal.agent == agent_state.key() @ ErrorCode::InvalidProposal
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        mut,
+        seeds = [b"agent", agent_state.authority.as_ref()],
+        bump = agent_state.bump
+    )]
+    pub agent_state: Account<'info, AgentState>,
+
+    #[account(
+        mut,
+        seeds = [b"governance", agent_state.key().as_ref()],
+        bump = governance.bump
+    )]
+    pub governance: Account<'info, GovernanceConfig>,
+
+    pub executor: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseProposal<'info> {
+    #[account(
+        mut,
+        seeds = [b"proposal", governance.agent.as_ref(), &proposal.proposal_id.to_le_bytes()],
+        bump = proposal.bump,
+        constraint = proposal.agent == governance.agent @ ErrorCode::InvalidProposal,
+        has_one = proposer,
+        close = proposer
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    #[account(
+        mut,
+        seeds = [b"governance", governance.agent.as_ref()],
+        bump = governance.bump
+    )]
+    pub governance: Account<'info, GovernanceConfig>,
+
+    #[account(mut)]
+    /// CHECK: Original proposer, receives the proposal rent
+    pub proposer: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct QueueConfigChange<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", agent_state.authority.as_ref()],
+        bump = agent_state.bump,
+        has_one = authority
+    )]
+    pub agent_state: Account<'info, AgentState>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + PendingConfigChange::INIT_SPACE,
+        seeds = [b"config_change", agent_state.key().as_ref(), &agent_state.config_change_count.to_le_bytes()],
+        bump
+    )]
+    pub pending_change: Account<'info, PendingConfigChange>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelConfigChange<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", agent_state.authority.as_ref()],
+        bump = agent_state.bump,
+        has_one = authority
+    )]
+    pub agent_state: Account<'info, AgentState>,
+
+    #[account(
+        mut,
+        seeds = [b"config_change", agent_state.key().as_ref(), &pending_change.change_id.to_le_bytes()],
+        bump = pending_change.bump,
+        constraint = pending_change.agent == agent_state.key() @ ErrorCode::InvalidConfigChange,
+        close = authority
+    )]
+    pub pending_change: Account<'info, PendingConfigChange>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteConfigChange<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", agent_state.authority.as_ref()],
+        bump = agent_state.bump,
+        has_one = authority
+    )]
+    pub agent_state: Account<'info, AgentState>,
+
+    #[account(
+        mut,
+        seeds = [b"config_change", agent_state.key().as_ref(), &pending_change.change_id.to_le_bytes()],
+        bump = pending_change.bump,
+        constraint = pending_change.agent == agent_state.key() @ ErrorCode::InvalidConfigChange,
+        close = authority
+    )]
+    pub pending_change: Account<'info, PendingConfigChange>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CheckCircuitBreaker<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", agent_state.authority.as_ref()],
+        bump = agent_state.bump
+    )]
+    pub agent_state: Account<'info, AgentState>,
+
+    #[account(
+        seeds = [b"vault", agent_state.key().as_ref()],
+        bump = agent_state.vault_bump
+    )]
+    /// CHECK: PDA vault
+    pub vault: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ResetCircuitBreaker<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", agent_state.authority.as_ref()],
+        bump = agent_state.bump,
+        has_one = authority
+    )]
+    pub agent_state: Account<'info, AgentState>,
+
+    #[account(
+        seeds = [b"vault", agent_state.key().as_ref()],
+        bump = agent_state.vault_bump
+    )]
+    /// CHECK: PDA vault
+    pub vault: UncheckedAccount<'info>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct OpenDepositReceipt<'info> {
+    #[account(
+        seeds = [b"agent", agent_state.authority.as_ref()],
+        bump = agent_state.bump
+    )]
+    pub agent_state: Account<'info, AgentState>,
+
+    #[account(
+        init,
+        payer = depositor,
+        space = 8 + DepositReceipt::INIT_SPACE,
+        seeds = [b"deposit", agent_state.key().as_ref(), depositor.key().as_ref()],
+        bump
+    )]
+    pub receipt: Account<'info, DepositReceipt>,
+
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct EmergencyWithdraw<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", agent_state.authority.as_ref()],
+        bump = agent_state.bump
+    )]
+    pub agent_state: Account<'info, AgentState>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", agent_state.key().as_ref()],
+        bump = agent_state.vault_bump
+    )]
+    /// CHECK: PDA vault
+    pub vault: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"deposit", agent_state.key().as_ref(), depositor.key().as_ref()],
+        bump = receipt.bump
+    )]
+    pub receipt: Account<'info, DepositReceipt>,
+
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct EmergencyRedeem<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", agent_state.authority.as_ref()],
+        bump = agent_state.bump
+    )]
+    pub agent_state: Account<'info, AgentState>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", agent_state.key().as_ref()],
+        bump = agent_state.vault_bump
+    )]
+    /// CHECK: PDA vault
+    pub vault: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = user
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut, address = agent_state.token_mint)]
+    pub token_mint: Account<'info, anchor_spl::token::Mint>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteBuyback<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", agent_state.authority.as_ref()],
+        bump = agent_state.bump
+    )]
+    pub agent_state: Account<'info, AgentState>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", agent_state.key().as_ref()],
+        bump = agent_state.vault_bump
+    )]
+    /// CHECK: PDA vault
+    pub vault: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"price_feed", agent_state.token_mint.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Account<'info, PriceFeed>,
+
+    #[account(mut, address = agent_state.token_mint)]
+    pub token_mint: Account<'info, anchor_spl::token::Mint>,
+
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = keeper
+    )]
+    pub keeper_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetRevenuePublisher<'info> {
+    #[account(
+        mut,
+        seeds = [b"platform"],
+        bump = platform.bump,
+        has_one = admin
+    )]
+    pub platform: Account<'info, PlatformConfig>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(merkle_root: [u8; 32], total_amount: u64, num_claimants: u32)]
+pub struct PublishRevenueEpoch<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", agent_state.authority.as_ref()],
+        bump = agent_state.bump
+    )]
+    pub agent_state: Account<'info, AgentState>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", agent_state.key().as_ref()],
+        bump = agent_state.vault_bump
+    )]
+    /// CHECK: PDA vault
+    pub vault: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = publisher,
+        space = RevenueEpoch::space(num_claimants),
+        seeds = [b"revenue_epoch", agent_state.key().as_ref(), &agent_state.revenue_epoch_count.to_le_bytes()],
+        bump
+    )]
+    pub epoch: Account<'info, RevenueEpoch>,
+
+    #[account(
+        seeds = [b"platform"],
+        bump = platform.bump,
+        constraint = platform.revenue_publisher == publisher.key() @ ErrorCode::UnauthorizedPublisher
+    )]
+    pub platform: Account<'info, PlatformConfig>,
+
+    #[account(mut)]
+    pub publisher: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimEpochRevenue<'info> {
+    #[account(
+        mut,
+        seeds = [b"revenue_epoch", epoch.agent.as_ref(), &epoch.epoch_id.to_le_bytes()],
+        bump = epoch.bump
+    )]
+    pub epoch: Account<'info, RevenueEpoch>,
+
+    #[account(mut)]
+    pub claimant: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseRevenueEpoch<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", agent_state.authority.as_ref()],
+        bump = agent_state.bump
+    )]
+    pub agent_state: Account<'info, AgentState>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", agent_state.key().as_ref()],
+        bump = agent_state.vault_bump
+    )]
+    /// CHECK: PDA vault
+    pub vault: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"revenue_epoch", agent_state.key().as_ref(), &epoch.epoch_id.to_le_bytes()],
+        bump = epoch.bump,
+        has_one = publisher,
+        close = publisher
+    )]
+    pub epoch: Account<'info, RevenueEpoch>,
+
+    #[account(mut)]
+    /// CHECK: Original publisher, receives the epoch rent
+    pub publisher: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateAgentState<'info> {
+    #[account(mut, owner = crate::ID)]
+    /// CHECK: Legacy agent state, decoded and checked in the handler
+    pub agent_state: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SpawnSubagent<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", parent_state.authority.as_ref()],
+        bump = parent_state.bump,
+        has_one = authority
+    )]
+    pub parent_state: Account<'info, AgentState>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", parent_state.key().as_ref()],
+        bump = parent_state.vault_bump
+    )]
+    /// CHECK: Parent PDA vault
+    pub parent_vault: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + AgentState::INIT_SPACE,
+        seeds = [b"agent", child_authority.key().as_ref()],
+        bump
+    )]
+    pub child_state: Account<'info, AgentState>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", child_state.key().as_ref()],
+        bump
+    )]
+    /// CHECK: Child PDA vault
+    pub child_vault: UncheckedAccount<'info>,
+
+    /// Key the parent operator uses to manage the sub-agent
+    pub child_authority: Signer<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RecallSubagent<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", parent_state.authority.as_ref()],
+        bump = parent_state.bump,
+        has_one = authority
+    )]
+    pub parent_state: Account<'info, AgentState>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", parent_state.key().as_ref()],
+        bump = parent_state.vault_bump
+    )]
+    /// CHECK: Parent PDA vault
+    pub parent_vault: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"agent", child_state.authority.as_ref()],
+        bump = child_state.bump,
+        constraint = child_state.parent == parent_state.key() @ ErrorCode::NotASubagent,
+        close = authority
+    )]
+    pub child_state: Account<'info, AgentState>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", child_state.key().as_ref()],
+        bump = child_state.vault_bump
+    )]
+    /// CHECK: Child PDA vault
+    pub child_vault: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"lending_position", child_state.key().as_ref()],
+        bump
+    )]
+    /// CHECK: Child lending position, if one was ever opened; checked in the handler
+    pub child_position: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"governance", child_state.key().as_ref()],
+        bump
+    )]
+    /// CHECK: Child governance, if it was ever initialized; checked in the handler
+    pub child_governance: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(strategy_id: u64)]
+pub struct ListStrategy<'info> {
+    #[account(
+        init,
+        payer = author,
+        space = 8 + Strategy::INIT_SPACE,
+        seeds = [b"strategy", author.key().as_ref(), &strategy_id.to_le_bytes()],
+        bump
+    )]
+    pub strategy: Account<'info, Strategy>,
+
+    #[account(mut)]
+    pub author: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateStrategy<'info> {
+    #[account(
+        mut,
+        seeds = [b"strategy", author.key().as_ref(), &strategy.strategy_id.to_le_bytes()],
+        bump = strategy.bump,
+        has_one = author
+    )]
+    pub strategy: Account<'info, Strategy>,
+
+    pub author: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct PurchaseLicense<'info> {
+    #[account(
+        mut,
+        seeds = [b"strategy", strategy.author.as_ref(), &strategy.strategy_id.to_le_bytes()],
+        bump = strategy.bump
+    )]
+    pub strategy: Account<'info, Strategy>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + StrategyLicense::INIT_SPACE,
+        seeds = [b"license", strategy.key().as_ref(), agent_state.key().as_ref()],
+        bump
+    )]
+    pub license: Account<'info, StrategyLicense>,
+
+    #[account(
+        seeds = [b"agent", agent_state.authority.as_ref()],
+        bump = agent_state.bump,
+        has_one = authority
+    )]
+    pub agent_state: Account<'info, AgentState>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RenewLicense<'info> {
+    #[account(
+        mut,
+        seeds = [b"strategy", strategy.author.as_ref(), &strategy.strategy_id.to_le_bytes()],
+        bump = strategy.bump
+    )]
+    pub strategy: Account<'info, Strategy>,
+
+    #[account(
+        mut,
+        seeds = [b"license", strategy.key().as_ref(), agent_state.key().as_ref()],
+        bump = license.bump
+    )]
+    pub license: Account<'info, StrategyLicense>,
+
+    #[account(
+        mut,
+        seeds = [b"agent", agent_state.authority.as_ref()],
+        bump = agent_state.bump,
+        has_one = authority
+    )]
+    pub agent_state: Account<'info, AgentState>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AdoptStrategy<'info> {
+    #[account(
+        seeds = [b"license", license.strategy.as_ref(), agent_state.key().as_ref()],
+        bump = license.bump
+    )]
+    pub license: Account<'info, StrategyLicense>,
+
+    #[account(
+        mut,
+        seeds = [b"agent", agent_state.authority.as_ref()],
+        bump = agent_state.bump,
+        has_one = authority
+    )]
+    pub agent_state: Account<'info, AgentState>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct PayStrategyRoyalties<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", agent_state.authority.as_ref()],
+        bump = agent_state.bump
+    )]
+    pub agent_state: Account<'info, AgentState>,
+
+    #[account(
+        mut,
+        seeds = [b"vault", agent_state.key().as_ref()],
+        bump = agent_state.vault_bump
+    )]
+    /// CHECK: PDA vault
+    pub vault: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"strategy", strategy.author.as_ref(), &strategy.strategy_id.to_le_bytes()],
+        bump = strategy.bump,
+        address = agent_state.strategy
+    )]
+    pub strategy: Account<'info, Strategy>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawStrategyProceeds<'info> {
+    #[account(
+        mut,
+        seeds = [b"strategy", author.key().as_ref(), &strategy.strategy_id.to_le_bytes()],
+        bump = strategy.bump,
+        has_one = author
+    )]
+    pub strategy: Account<'info, Strategy>,
+
+    #[account(mut)]
+    pub author: Signer<'info>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct AgentState {
+    pub version: u8,
+    pub authority: Pubkey,
+    pub agent_wallet: Pubkey,
+    pub token_mint: Pubkey,
+    pub vault: Pubkey,
+    #[max_len(32)]
+    pub name: String,
+    #[max_len(200)]
+    pub purpose: String,
+    pub state: AgentStatus,
+    pub total_trades: u64,
+    pub total_volume: u64,
+    pub revenue_pool: u64,
+    pub order_count: u64,
+    pub dca_count: u64,
+    /// Share of follower profits paid to this agent when it is copied
+    pub copy_fee_bps: u16,
+    pub last_trade: TradeSnapshot,
+    pub risk_config: RiskConfig,
+    /// Mints the agent may trade besides SOL, empty allows any mint
+    #[max_len(10)]
+    pub trading_whitelist: Vec<Pubkey>,
+    /// Delay in seconds before queued configuration changes can execute
+    pub config_delay: i64,
+    pub config_change_count: u64,
+    pub consecutive_losses: u16,
+    /// Realized PnL in the current 24h window
+    pub daily_pnl: i64,
+    pub day_start_balance: u64,
+    pub day_started_at: i64,
+    pub circuit_broken_at: i64,
+    pub paused_at: i64,
+    /// Seconds an agent must stay out of `Active` before depositors can exit on their own
+    pub emergency_grace_period: i64,
+    /// Deposits recorded against deposit receipts
+    pub total_deposits: u64,
+    pub revenue_mode: RevenueMode,
+    /// Revenue earmarked for token buybacks
+    pub buyback_pool: u64,
+    pub total_burned: u64,
+    pub revenue_epoch_count: u64,
+    pub bump: u8,
+    pub vault_bump: u8,
+    /// Parent agent of a sub-agent, default for top-level agents
+    pub parent: Pubkey,
+    /// Funds delegated by the parent when the sub-agent was spawned
+    pub subagent_budget: u64,
+    pub subagent_count: u32,
+    /// Funds currently delegated to this agent's sub-agents
+    pub delegated_budget: u64,
+    /// Licensed strategy the agent runs, default when none
+    pub strategy: Pubkey,
+    /// Author's share of performance fees under the license
+    pub strategy_royalty_bps: u16,
+    /// When the license stops earning royalties, 0 for perpetual licenses
+    pub strategy_expires_at: i64,
+    /// Royalties owed to the strategy author, held in the vault until paid
+    pub strategy_royalties: u64,
+    /// Set while the agent owes the lending pool; vault outflows are blocked
+    pub has_debt: bool,
+    /// Orders and DCA schedules not yet executed, completed or cancelled
+    pub open_orders: u16,
+    pub open_dca_schedules: u16,
+    /// Follower vaults currently copying this agent
+    pub follower_count: u16,
+    pub retired_at: i64,
+    /// Published revenue epochs not yet closed
+    pub open_revenue_epochs: u8,
+    /// Queued config changes not yet executed or cancelled
+    pub pending_config_changes: u8,
+    /// Set when token holders paused the agent; only a proposal can resume it
+    pub governance_paused: bool,
+    /// Lamports moved into the vault by this program, less what it paid out.
+    /// Transfers that bypass the program are not counted.
+    pub vault_balance: u64,
+}
+
+/// `AgentState` layout before versioning, kept to migrate existing agents
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace)]
+pub struct AgentStateV0 {
+    pub authority: Pubkey,
+    pub agent_wallet: Pubkey,
+    pub token_mint: Pubkey,
+    pub vault: Pubkey,
+    #[max_len(32)]
+    pub name: String,
+    #[max_len(200)]
+    pub purpose: String,
+    pub state: AgentStatus,
+    pub total_trades: u64,
+    pub total_volume: u64,
+    pub revenue_pool: u64,
+    pub bump: u8,
+    pub vault_bump: u8,
+}
+
+impl AgentStateV0 {
+    pub fn migrate(self) -> AgentState {
+        AgentState {
+            state: self.state,
+            total_trades: self.total_trades,
+            total_volume: self.total_volume,
+            revenue_pool: self.revenue_pool,
+            ..AgentState::new(
+                self.authority,
+                self.agent_wallet,
+                self.token_mint,
+                self.vault,
+                self.name,
+                self.purpose,
+                self.bump,
+                self.vault_bump,
+            )
+        }
+    }
+}
+
+impl AgentState {
+    /// A fresh active agent on the current layout
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        authority: Pubkey,
+        agent_wallet: Pubkey,
+        token_mint: Pubkey,
+        vault: Pubkey,
+        name: String,
+        purpose: String,
+        bump: u8,
+        vault_bump: u8,
+    ) -> Self {
+        Self {
+            version: AGENT_STATE_VERSION,
+            authority,
+            agent_wallet,
+            token_mint,
+            vault,
+            name,
+            purpose,
+            state: AgentStatus::Active,
+            total_trades: 0,
+            total_volume: 0,
+            revenue_pool: 0,
+            order_count: 0,
+            dca_count: 0,
+            copy_fee_bps: 0,
+            last_trade: TradeSnapshot::default(),
+            risk_config: RiskConfig::default(),
+            trading_whitelist: Vec::new(),
+            config_delay: DEFAULT_CONFIG_DELAY,
+            config_change_count: 0,
+            consecutive_losses: 0,
+            daily_pnl: 0,
+            day_start_balance: 0,
+            day_started_at: 0,
+            circuit_broken_at: 0,
+            paused_at: 0,
+            emergency_grace_period: DEFAULT_EMERGENCY_GRACE_PERIOD,
+            total_deposits: 0,
+            revenue_mode: RevenueMode::Distribute,
+            buyback_pool: 0,
+            total_burned: 0,
+            revenue_epoch_count: 0,
+            bump,
+            vault_bump,
+            parent: Pubkey::default(),
+            subagent_budget: 0,
+            subagent_count: 0,
+            delegated_budget: 0,
+            strategy: Pubkey::default(),
+            strategy_royalty_bps: 0,
+            strategy_expires_at: 0,
+            strategy_royalties: 0,
+            has_debt: false,
+            open_orders: 0,
+            open_dca_schedules: 0,
+            follower_count: 0,
+            retired_at: 0,
+            open_revenue_epochs: 0,
+            pending_config_changes: 0,
+            governance_paused: false,
+            vault_balance: 0,
+        }
+    }
+
+    pub fn is_subagent(&self) -> bool {
+        self.parent != Pubkey::default()
+    }
+
+    /// Vault balance not owed to holders or to the strategy author
+    pub fn free_balance(&self, vault_balance: u64) -> u64 {
+        vault_balance
+            .saturating_sub(self.revenue_pool)
+            .saturating_sub(self.buyback_pool)
+            .saturating_sub(self.strategy_royalties)
+    }
+
+    /// Record lamports this program moved into the vault
+    pub fn credit_vault(&mut self, amount: u64) -> Result<()> {
+        self.vault_balance = self
+            .vault_balance
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Record lamports this program paid out of the vault
+    pub fn debit_vault(&mut self, amount: u64) {
+        self.vault_balance = self.vault_balance.saturating_sub(amount);
+    }
+
+    /// Vault lamports that arrived through this program, ignoring transfers
+    /// made straight to the vault address
+    pub fn tracked_vault_balance(&self, vault_lamports: u64) -> u64 {
+        self.vault_balance.min(vault_lamports)
+    }
+
+    /// Whether performance fees currently owe a strategy royalty
+    pub fn strategy_license_active(&self, now: i64) -> bool {
+        self.strategy != Pubkey::default()
+            && (self.strategy_expires_at == 0 || now < self.strategy_expires_at)
+    }
+
+    /// Whether the agent has been out of `Active` long enough to open the
+    /// emergency exit, counted from when its current status began
+    pub fn emergency_exit_open(&self, now: i64) -> bool {
+        let since = match self.state {
+            AgentStatus::Active => return false,
+            AgentStatus::Paused => self.paused_at,
+            AgentStatus::CircuitBroken => self.circuit_broken_at,
+            AgentStatus::Retired => self.retired_at,
+        };
+        now >= since.saturating_add(self.emergency_grace_period)
+    }
+
+    pub fn can_trade(&self, mint: &Pubkey) -> bool {
+        *mint == NATIVE_MINT
+            || self.trading_whitelist.is_empty()
+            || self.trading_whitelist.contains(mint)
+    }
+
+    /// Track the losing streak and the rolling 24h realized PnL
+    pub fn record_trade_result(
+        &mut self,
+        amount_in: u64,
+        amount_out: u64,
+        vault_balance: u64,
+        now: i64,
+    ) {
+        if now.saturating_sub(self.day_started_at) >= SECONDS_PER_DAY {
+            self.day_started_at = now;
+            self.day_start_balance = vault_balance;
+            self.daily_pnl = 0;
+        }
+
+        let pnl = (amount_out as i128 - amount_in as i128)
+            .clamp(i64::MIN as i128, i64::MAX as i128) as i64;
+        self.daily_pnl = self.daily_pnl.saturating_add(pnl);
+
+        if amount_out < amount_in {
+            self.consecutive_losses = self.consecutive_losses.saturating_add(1);
+        } else {
+            self.consecutive_losses = 0;
+        }
+    }
+
+    /// The first breached circuit breaker threshold, if any
+    pub fn circuit_breaker_cause(&self, vault_balance: u64) -> Option<CircuitBreakerCause> {
+        if self.state != AgentStatus::Active {
+            return None;
+        }
+
+        let risk = &self.risk_config;
+        if risk.max_consecutive_losses > 0 && self.consecutive_losses >= risk.max_consecutive_losses {
+            return Some(CircuitBreakerCause::ConsecutiveLosses);
+        }
+        if risk.max_daily_drawdown_bps > 0
+            && self.daily_pnl < 0
+            && self.daily_pnl.unsigned_abs() > bps_of(self.day_start_balance, risk.max_daily_drawdown_bps)
+        {
+            return Some(CircuitBreakerCause::DailyDrawdown);
+        }
+        if risk.min_vault_balance > 0 && vault_balance < risk.min_vault_balance {
+            return Some(CircuitBreakerCause::VaultBelowFloor);
+        }
+        None
+    }
+}
+
+/// The most recent trade booked by an agent, read by copy-trading cranks
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default, InitSpace)]
+pub struct TradeSnapshot {
+    pub trade_id: u64,
+    pub from_mint: Pubkey,
+    pub to_mint: Pubkey,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    /// Vault balance before the trade, used as the leader's NAV
+    pub vault_balance: u64,
+    pub timestamp: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub enum AgentStatus {
+    Active,
+    Paused,
+    Retired,
+    CircuitBroken,
+}
+
+/// How holder revenue is used: claimed as SOL, spent on buyback-and-burn, or split
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub enum RevenueMode {
+    Distribute,
+    BuybackBurn,
+    Split { buyback_bps: u16 },
+}
+
+impl RevenueMode {
+    pub fn validate(&self) -> Result<()> {
+        if let RevenueMode::Split { buyback_bps } = self {
+            require!(*buyback_bps <= MAX_BPS, ErrorCode::InvalidConfigChange);
+        }
+        Ok(())
+    }
+
+    pub fn buyback_share(&self, revenue: u64) -> u64 {
+        match self {
+            RevenueMode::Distribute => 0,
+            RevenueMode::BuybackBurn => revenue,
+            RevenueMode::Split { buyback_bps } => bps_of(revenue, *buyback_bps),
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub enum CircuitBreakerCause {
+    ConsecutiveLosses,
+    DailyDrawdown,
+    VaultBelowFloor,
+}
+
+/// Per-agent trading limits, zero disables a limit
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default, InitSpace)]
+pub struct RiskConfig {
+    /// Largest single trade in input units
+    pub max_trade_amount: u64,
+    /// Largest single trade as a share of the vault balance
+    pub max_trade_bps: u16,
+    /// Losing trades in a row that trip the circuit breaker
+    pub max_consecutive_losses: u16,
+    /// Realized 24h loss, relative to the day's opening balance, that trips the breaker
+    pub max_daily_drawdown_bps: u16,
+    /// Vault balance floor that trips the breaker
+    pub min_vault_balance: u64,
+    /// Seconds a tripped agent must wait before it can be reset
+    pub breaker_cooldown: i64,
+}
+
+impl RiskConfig {
+    pub fn validate(&self) -> Result<()> {
+        require!(self.max_trade_bps <= MAX_BPS, ErrorCode::InvalidRiskConfig);
+        require!(self.max_daily_drawdown_bps <= MAX_BPS, ErrorCode::InvalidRiskConfig);
+        require!(self.breaker_cooldown >= 0, ErrorCode::InvalidRiskConfig);
+        Ok(())
+    }
+
+    pub fn check_trade(&self, amount: u64, vault_balance: u64) -> Result<()> {
+        require!(
+            self.max_trade_amount == 0 || amount <= self.max_trade_amount,
+            ErrorCode::RiskLimitExceeded
+        );
+        require!(
+            self.max_trade_bps == 0 || amount <= bps_of(vault_balance, self.max_trade_bps),
+            ErrorCode::RiskLimitExceeded
+        );
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct PlatformConfig {
+    pub admin: Pubkey,
+    pub treasury: Pubkey,
+    /// Signer allowed to publish merkle revenue epochs
+    pub revenue_publisher: Pubkey,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct PriceFeed {
+    pub token_mint: Pubkey,
+    pub oracle: Pubkey,
+    /// Lamports per token base unit, scaled by `PRICE_SCALE`
+    pub price: u64,
+    pub updated_at: i64,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Order {
+    pub agent: Pubkey,
+    pub order_id: u64,
+    pub order_type: OrderType,
+    pub token_mint: Pubkey,
+    /// Lamports to spend for buys, token base units to sell otherwise
+    pub amount: u64,
+    pub trigger_price: u64,
+    pub keeper_tip: u64,
+    pub created_at: i64,
+    /// Zero means the order never expires
+    pub expires_at: i64,
+    pub bump: u8,
+}
+
+impl Order {
+    pub fn is_triggered(&self, price: u64) -> bool {
+        match self.order_type {
+            // Trigger if price falls to or below the trigger
+            OrderType::StopLoss | OrderType::LimitBuy => price <= self.trigger_price,
+            // Trigger if price rises to or above the trigger
+            OrderType::TakeProfit | OrderType::LimitSell => price >= self.trigger_price,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub enum OrderType {
+    StopLoss,
+    TakeProfit,
+    LimitBuy,
+    LimitSell,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct DcaSchedule {
+    pub agent: Pubkey,
+    pub schedule_id: u64,
+    /// One side of the pair must be `NATIVE_MINT`
+    pub input_mint: Pubkey,
+    pub output_mint: Pubkey,
+    pub amount_per_interval: u64,
+    pub interval_seconds: i64,
+    pub total_cycles: u32,
+    pub completed_cycles: u32,
+    /// Allowance below the oracle quote that each cycle is filled at
+    pub max_slippage_bps: u16,
+    pub next_execution_at: i64,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct FollowerState {
+    pub leader: Pubkey,
+    pub owner: Pubkey,
+    /// Cap on the lamports committed to any single mirrored trade
+    pub max_trade_amount: u64,
+    /// Highest leader fee the follower accepts
+    pub max_fee_bps: u16,
+    pub last_mirrored_trade: u64,
+    pub total_trades: u64,
+    pub total_volume: u64,
+    pub fees_paid: u64,
+    /// Lamports held at cost in open positions
+    pub committed: u64,
+    #[max_len(MAX_FOLLOWER_POSITIONS)]
+    pub positions: Vec<FollowerPosition>,
+    pub bump: u8,
+    pub vault_bump: u8,
+}
+
+/// A token position opened by mirroring leader buys, valued at cost
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct FollowerPosition {
+    pub mint: Pubkey,
+    pub tokens: u64,
+    pub cost: u64,
+}
+
+impl FollowerState {
+    pub fn open_position(&mut self, mint: Pubkey, tokens: u64, cost: u64) -> Result<()> {
+        match self.positions.iter_mut().find(|p| p.mint == mint) {
+            Some(position) => {
+                position.tokens = position
+                    .tokens
+                    .checked_add(tokens)
+                    .ok_or(ErrorCode::MathOverflow)?;
+                position.cost = position.cost.checked_add(cost).ok_or(ErrorCode::MathOverflow)?;
+            }
+            None => {
+                require!(
+                    self.positions.len() < MAX_FOLLOWER_POSITIONS,
+                    ErrorCode::TooManyPositions
+                );
+                self.positions.push(FollowerPosition { mint, tokens, cost });
+            }
+        }
+
+        self.committed = self.committed.checked_add(cost).ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Sell `tokens` out of a position, returning the cost basis released
+    pub fn close_position(&mut self, index: usize, tokens: u64) -> Result<u64> {
+        let position = &mut self.positions[index];
+        let cost = mul_div(position.cost, tokens, position.tokens)?;
+
+        position.tokens -= tokens;
+        position.cost -= cost;
+        if position.tokens == 0 {
+            self.positions.swap_remove(index);
+        }
+
+        self.committed = self.committed.saturating_sub(cost);
+        Ok(cost)
+    }
+}
+
+/// Interest rate curve and risk parameters of the lending pool, in bps
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct LendingPoolParams {
+    pub base_rate_bps: u16,
+    /// Rate added between zero and optimal utilization
+    pub slope1_bps: u16,
+    /// Rate added between optimal and full utilization
+    pub slope2_bps: u16,
+    pub optimal_utilization_bps: u16,
+    pub max_ltv_bps: u16,
+    pub liquidation_threshold_bps: u16,
+    pub liquidation_bonus_bps: u16,
+}
+
+impl LendingPoolParams {
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.optimal_utilization_bps > 0 && self.optimal_utilization_bps < MAX_BPS,
+            ErrorCode::InvalidLendingParams
+        );
+        require!(
+            self.max_ltv_bps < self.liquidation_threshold_bps
+                && self.liquidation_threshold_bps < MAX_BPS,
+            ErrorCode::InvalidLendingParams
+        );
+        require!(self.liquidation_bonus_bps <= 2_000, ErrorCode::InvalidLendingParams);
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct LendingPool {
+    pub params: LendingPoolParams,
+    pub available_liquidity: u64,
+    /// Outstanding debt including accrued interest
+    pub total_borrowed: u64,
+    pub total_shares: u64,
+    /// Cumulative borrow interest index, scaled by `INDEX_SCALE`
+    pub borrow_index: u128,
+    pub last_accrual_at: i64,
+    pub bump: u8,
+    pub vault_bump: u8,
+}
+
+impl LendingPool {
+    pub fn total_assets(&self) -> u64 {
+        self.available_liquidity.saturating_add(self.total_borrowed)
+    }
+
+    pub fn utilization_bps(&self) -> u64 {
+        let total = self.total_assets();
+        if total == 0 {
+            return 0;
+        }
+        (self.total_borrowed as u128 * MAX_BPS as u128 / total as u128) as u64
+    }
+
+    /// Annual borrow rate from the kinked utilization curve
+    pub fn borrow_rate_bps(&self) -> u64 {
+        let params = &self.params;
+        let utilization = self.utilization_bps();
+        let optimal = params.optimal_utilization_bps as u64;
+
+        if utilization <= optimal {
+            params.base_rate_bps as u64 + params.slope1_bps as u64 * utilization / optimal
+        } else {
+            params.base_rate_bps as u64
+                + params.slope1_bps as u64
+                + params.slope2_bps as u64 * (utilization - optimal) / (MAX_BPS as u64 - optimal)
+        }
+    }
+
+    /// Compound interest into the borrow index and outstanding debt
+    pub fn accrue(&mut self, now: i64) -> Result<()> {
+        let elapsed = now.saturating_sub(self.last_accrual_at);
+        if elapsed <= 0 {
+            return Ok(());
+        }
+
+        let interest_factor = (self.borrow_rate_bps() as u128)
+            .checked_mul(elapsed as u128)
+            .and_then(|v| v.checked_mul(INDEX_SCALE))
+            .ok_or(ErrorCode::MathOverflow)?
+            / (SECONDS_PER_YEAR * MAX_BPS as u128);
+
+        let index_growth = self
+            .borrow_index
+            .checked_mul(interest_factor)
+            .ok_or(ErrorCode::MathOverflow)?
+            / INDEX_SCALE;
+        let new_index = self
+            .borrow_index
+            .checked_add(index_growth)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        self.total_borrowed = u64::try_from(
+            (self.total_borrowed as u128)
+                .checked_mul(new_index)
+                .ok_or(ErrorCode::MathOverflow)?
+                / self.borrow_index,
+        )
+        .map_err(|_| error!(ErrorCode::MathOverflow))?;
+        self.borrow_index = new_index;
+        self.last_accrual_at = now;
+        Ok(())
+    }
+
+    pub fn shares_for_deposit(&self, amount: u64) -> Result<u64> {
+        if self.total_shares == 0 || self.total_assets() == 0 {
+            return Ok(amount);
+        }
+        u64::try_from(amount as u128 * self.total_shares as u128 / self.total_assets() as u128)
+            .map_err(|_| error!(ErrorCode::MathOverflow))
+    }
+
+    pub fn assets_for_shares(&self, shares: u64) -> Result<u64> {
+        require!(self.total_shares > 0, ErrorCode::InvalidAmount);
+        u64::try_from(shares as u128 * self.total_assets() as u128 / self.total_shares as u128)
+            .map_err(|_| error!(ErrorCode::MathOverflow))
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct LendingPosition {
+    pub agent: Pubkey,
+    pub supply_shares: u64,
+    pub debt_principal: u64,
+    /// Pool borrow index when the debt was last updated
+    pub borrow_index: u128,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct GovernanceConfig {
+    pub agent: Pubkey,
+    /// Share of token supply that must vote for a proposal to be valid
+    pub quorum_bps: u16,
+    pub voting_period: i64,
+    /// Delay between the end of voting and execution
+    pub timelock: i64,
+    /// Escrowed tokens required to create a proposal
+    pub proposal_threshold: u64,
+    pub proposal_count: u64,
+    /// Proposals neither executed nor closed after failing
+    pub open_proposals: u32,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct VoterEscrow {
+    pub agent: Pubkey,
+    pub owner: Pubkey,
+    /// Escrowed agent tokens, used as voting weight
+    pub amount: u64,
+    /// Tokens cannot be withdrawn before the last voted proposal closes
+    pub locked_until: i64,
+    /// Last deposit; the escrow only votes on proposals created after it
+    pub deposited_at: i64,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Proposal {
+    pub agent: Pubkey,
+    pub proposal_id: u64,
+    pub proposer: Pubkey,
+    pub action: GovernanceAction,
+    pub votes_for: u64,
+    pub votes_against: u64,
+    pub quorum_votes: u64,
+    pub voting_ends_at: i64,
+    /// Earliest execution time, voting end plus the timelock
+    pub eta: i64,
+    pub created_at: i64,
+    pub executed: bool,
+    pub bump: u8,
+}
+
+impl Proposal {
+    pub fn has_passed(&self) -> bool {
+        self.votes_for > self.votes_against
+            && self.votes_for.saturating_add(self.votes_against) >= self.quorum_votes
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct VoteRecord {
+    pub proposal: Pubkey,
+    pub voter: Pubkey,
+    pub weight: u64,
+    pub support: bool,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub enum GovernanceAction {
+    ChangePurpose {
+        #[max_len(200)]
+        purpose: String,
+    },
+    RotateAgentWallet {
+        agent_wallet: Pubkey,
+    },
+    Pause,
+    /// Lift a pause, including one governance imposed
+    Resume,
+    UpdateRiskConfig {
+        risk_config: RiskConfig,
+    },
+    Retire,
+}
+
+impl GovernanceAction {
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            GovernanceAction::ChangePurpose { purpose } => {
+                require!(!purpose.is_empty() && purpose.len() <= 200, ErrorCode::InvalidPurpose);
+            }
+            GovernanceAction::RotateAgentWallet { agent_wallet } => {
+                require!(*agent_wallet != Pubkey::default(), ErrorCode::InvalidProposal);
+            }
+            GovernanceAction::UpdateRiskConfig { risk_config } => risk_config.validate()?,
+            GovernanceAction::Pause | GovernanceAction::Resume | GovernanceAction::Retire => {}
+        }
+        Ok(())
+    }
+}
+
+#[account]
+pub struct RevenueEpoch {
+    pub agent: Pubkey,
+    pub epoch_id: u64,
+    pub publisher: Pubkey,
+    pub merkle_root: [u8; 32],
+    pub total_amount: u64,
+    pub claimed_amount: u64,
+    pub num_claimants: u32,
+    pub published_at: i64,
+    /// After expiry claims stop and unclaimed SOL rolls over
+    pub expires_at: i64,
+    pub bump: u8,
+    /// One bit per claimant index, set once claimed
+    pub claimed_bitmap: Vec<u8>,
+}
+
+impl RevenueEpoch {
+    pub fn bitmap_len(num_claimants: u32) -> usize {
+        (num_claimants as usize).div_ceil(8)
+    }
+
+    pub fn space(num_claimants: u32) -> usize {
+        8 + 32 + 8 + 32 + 32 + 8 + 8 + 4 + 8 + 8 + 1 + 4 + Self::bitmap_len(num_claimants)
+    }
+
+    pub fn is_claimed(&self, index: u32) -> bool {
+        self.claimed_bitmap[index as usize / 8] & (1 << (index % 8)) != 0
+    }
+
+    pub fn set_claimed(&mut self, index: u32) {
+        self.claimed_bitmap[index as usize / 8] |= 1 << (index % 8);
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Strategy {
+    pub author: Pubkey,
+    pub strategy_id: u64,
+    /// Hash of the off-chain strategy content licensees receive
+    pub content_hash: [u8; 32],
+    /// Price of a perpetual license, or of one subscription period
+    pub price: u64,
+    pub license_model: LicenseModel,
+    /// Share of licensees' performance fees paid to the author
+    pub royalty_bps: u16,
+    /// Inactive strategies can't be licensed or renewed
+    pub active: bool,
+    pub license_count: u64,
+    /// Sales and royalties held in escrow for the author
+    pub proceeds: u64,
+    pub total_sales: u64,
+    pub total_royalties: u64,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub enum LicenseModel {
+    Perpetual,
+    /// Licenses run for `period` seconds and are renewed at the current price
+    Subscription { period: i64 },
+}
+
+impl LicenseModel {
+    pub fn validate(&self) -> Result<()> {
+        if let LicenseModel::Subscription { period } = self {
+            require!(*period > 0, ErrorCode::InvalidLicenseModel);
+        }
+        Ok(())
+    }
+
+    /// Expiry of a license bought or renewed at `now`, 0 for perpetual licenses
+    pub fn expiry(&self, now: i64, current_expiry: i64) -> i64 {
+        match self {
+            LicenseModel::Perpetual => 0,
+            LicenseModel::Subscription { period } => now.max(current_expiry).saturating_add(*period),
+        }
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct StrategyLicense {
+    pub strategy: Pubkey,
+    pub agent: Pubkey,
+    /// Royalty locked in when the license was bought
+    pub royalty_bps: u16,
+    pub purchased_at: i64,
+    /// 0 for perpetual licenses
+    pub expires_at: i64,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct DepositReceipt {
+    pub agent: Pubkey,
+    pub depositor: Pubkey,
+    pub amount: u64,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct PendingConfigChange {
+    pub agent: Pubkey,
+    pub change_id: u64,
+    pub change: ConfigChange,
+    pub queued_at: i64,
+    /// Earliest execution time, queue time plus the agent's config delay
+    pub eta: i64,
+    pub bump: u8,
+}
+
+/// Sensitive agent settings that only change after the agent's config delay
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub enum ConfigChange {
+    AgentWallet {
+        agent_wallet: Pubkey,
+    },
+    RiskConfig {
+        risk_config: RiskConfig,
+    },
+    CopyFee {
+        copy_fee_bps: u16,
+    },
+    TradingWhitelist {
+        #[max_len(10)]
+        mints: Vec<Pubkey>,
+    },
+    ConfigDelay {
+        delay: i64,
+    },
+    EmergencyGracePeriod {
+        grace_period: i64,
+    },
+    RevenueMode {
+        revenue_mode: RevenueMode,
+    },
+}
+
+impl ConfigChange {
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            ConfigChange::AgentWallet { agent_wallet } => {
+                require!(*agent_wallet != Pubkey::default(), ErrorCode::InvalidConfigChange);
+            }
+            ConfigChange::RiskConfig { risk_config } => risk_config.validate()?,
+            ConfigChange::CopyFee { copy_fee_bps } => {
+                require!(*copy_fee_bps <= MAX_COPY_FEE_BPS, ErrorCode::FeeTooHigh);
+            }
+            ConfigChange::TradingWhitelist { mints } => {
+                require!(mints.len() <= 10, ErrorCode::InvalidConfigChange);
+            }
+            ConfigChange::ConfigDelay { delay } => {
+                require!(
+                    *delay >= 0 && *delay <= MAX_CONFIG_DELAY,
+                    ErrorCode::InvalidConfigChange
+                );
+            }
+            ConfigChange::EmergencyGracePeriod { grace_period } => {
+                require!(
+                    (MIN_EMERGENCY_GRACE_PERIOD..=MAX_EMERGENCY_GRACE_PERIOD).contains(grace_period),
+                    ErrorCode::InvalidConfigChange
+                );
+            }
+            ConfigChange::RevenueMode { revenue_mode } => revenue_mode.validate()?,
+        }
+        Ok(())
+    }
+}
+
+impl LendingPosition {
+    pub fn current_debt(&self, pool: &LendingPool) -> Result<u64> {
+        if self.debt_principal == 0 {
+            return Ok(0);
+        }
+        u64::try_from(self.debt_principal as u128 * pool.borrow_index / self.borrow_index)
+            .map_err(|_| error!(ErrorCode::MathOverflow))
+    }
+}
+
+#[event]
+pub struct FundsDepositedEvent {
+    pub agent: Pubkey,
+    pub depositor: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TradeExecutedEvent {
+    pub agent: Pubkey,
+    pub from_mint: Pubkey,
+    pub to_mint: Pubkey,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RevenueClaimedEvent {
+    pub agent: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PurposeUpdatedEvent {
+    pub agent: Pubkey,
+    pub old_purpose: String,
+    pub new_purpose: String,
+    pub timestamp: i64,
+}
 
 #[event]
 pub struct AgentStatusChangedEvent {
@@ -482,6 +5177,332 @@ pub struct AgentStatusChangedEvent {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct PriceUpdatedEvent {
+    pub token_mint: Pubkey,
+    pub price: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OrderPlacedEvent {
+    pub agent: Pubkey,
+    pub order: Pubkey,
+    pub order_type: OrderType,
+    pub token_mint: Pubkey,
+    pub amount: u64,
+    pub trigger_price: u64,
+    pub keeper_tip: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OrderCancelledEvent {
+    pub agent: Pubkey,
+    pub order: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OrderExecutedEvent {
+    pub agent: Pubkey,
+    pub order: Pubkey,
+    pub keeper: Pubkey,
+    pub price: u64,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub keeper_tip: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DcaScheduleCreatedEvent {
+    pub agent: Pubkey,
+    pub schedule: Pubkey,
+    pub input_mint: Pubkey,
+    pub output_mint: Pubkey,
+    pub amount_per_interval: u64,
+    pub interval_seconds: i64,
+    pub total_cycles: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DcaCycleExecutedEvent {
+    pub agent: Pubkey,
+    pub schedule: Pubkey,
+    pub cycle: u32,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub price: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DcaScheduleClosedEvent {
+    pub agent: Pubkey,
+    pub schedule: Pubkey,
+    pub completed_cycles: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AgentFollowedEvent {
+    pub leader: Pubkey,
+    pub follower: Pubkey,
+    pub owner: Pubkey,
+    pub deposit: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TradeMirroredEvent {
+    pub leader: Pubkey,
+    pub follower: Pubkey,
+    pub leader_trade_id: u64,
+    pub from_mint: Pubkey,
+    pub to_mint: Pubkey,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub leader_fee: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TradeMirrorSkippedEvent {
+    pub leader: Pubkey,
+    pub follower: Pubkey,
+    pub leader_trade_id: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FollowerResyncedEvent {
+    pub leader: Pubkey,
+    pub follower: Pubkey,
+    pub from_trade_id: u64,
+    pub to_trade_id: u64,
+    pub skipped: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AgentUnfollowedEvent {
+    pub leader: Pubkey,
+    pub follower: Pubkey,
+    pub owner: Pubkey,
+    pub withdrawn: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolSuppliedEvent {
+    pub agent: Pubkey,
+    pub amount: u64,
+    pub shares: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolWithdrawnEvent {
+    pub agent: Pubkey,
+    pub amount: u64,
+    pub shares: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolBorrowedEvent {
+    pub agent: Pubkey,
+    pub amount: u64,
+    pub total_debt: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LoanRepaidEvent {
+    pub agent: Pubkey,
+    pub amount: u64,
+    pub remaining_debt: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PositionLiquidatedEvent {
+    pub agent: Pubkey,
+    pub liquidator: Pubkey,
+    pub repaid: u64,
+    pub seized: u64,
+    pub remaining_debt: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProposalCreatedEvent {
+    pub agent: Pubkey,
+    pub proposal: Pubkey,
+    pub proposer: Pubkey,
+    pub action: GovernanceAction,
+    pub voting_ends_at: i64,
+    pub eta: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VoteCastEvent {
+    pub proposal: Pubkey,
+    pub voter: Pubkey,
+    pub support: bool,
+    pub weight: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProposalExecutedEvent {
+    pub agent: Pubkey,
+    pub proposal: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProposalClosedEvent {
+    pub agent: Pubkey,
+    pub proposal: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ConfigChangeQueuedEvent {
+    pub agent: Pubkey,
+    pub pending_change: Pubkey,
+    pub change: ConfigChange,
+    pub eta: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ConfigChangeCancelledEvent {
+    pub agent: Pubkey,
+    pub pending_change: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ConfigChangeExecutedEvent {
+    pub agent: Pubkey,
+    pub pending_change: Pubkey,
+    pub change: ConfigChange,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CircuitBreakerTrippedEvent {
+    pub agent: Pubkey,
+    pub cause: CircuitBreakerCause,
+    pub consecutive_losses: u16,
+    pub daily_pnl: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EmergencyWithdrawalEvent {
+    pub agent: Pubkey,
+    pub user: Pubkey,
+    pub amount: u64,
+    pub tokens_burned: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BuybackExecutedEvent {
+    pub agent: Pubkey,
+    pub keeper: Pubkey,
+    pub lamports_spent: u64,
+    pub tokens_burned: u64,
+    pub price: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RevenueEpochPublishedEvent {
+    pub agent: Pubkey,
+    pub epoch: Pubkey,
+    pub epoch_id: u64,
+    pub merkle_root: [u8; 32],
+    pub total_amount: u64,
+    pub num_claimants: u32,
+    pub expires_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RevenueEpochClosedEvent {
+    pub agent: Pubkey,
+    pub epoch: Pubkey,
+    pub epoch_id: u64,
+    pub rolled_over: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AccountMigratedEvent {
+    pub account: Pubkey,
+    pub version: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SubagentSpawnedEvent {
+    pub parent: Pubkey,
+    pub child: Pubkey,
+    pub child_authority: Pubkey,
+    pub budget: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SubagentRecalledEvent {
+    pub parent: Pubkey,
+    pub child: Pubkey,
+    pub returned: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StrategyListedEvent {
+    pub strategy: Pubkey,
+    pub author: Pubkey,
+    pub content_hash: [u8; 32],
+    pub price: u64,
+    pub royalty_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LicensePurchasedEvent {
+    pub strategy: Pubkey,
+    pub agent: Pubkey,
+    pub price: u64,
+    pub expires_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StrategyAdoptedEvent {
+    pub agent: Pubkey,
+    pub strategy: Pubkey,
+    pub royalty_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StrategyRoyaltiesPaidEvent {
+    pub strategy: Pubkey,
+    pub agent: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
 #[error_code]
 pub enum ErrorCode {
     #[msg("Invalid amount specified")]
@@ -504,4 +5525,132 @@ pub enum ErrorCode {
     AgentAlreadyPaused,
     #[msg("Agent is already active")]
     AgentAlreadyActive,
+    #[msg("Price must be greater than zero")]
+    InvalidPrice,
+    #[msg("Price feed is stale")]
+    StalePrice,
+    #[msg("Order does not belong to this agent")]
+    InvalidOrder,
+    #[msg("Order has expired")]
+    OrderExpired,
+    #[msg("Order trigger price not reached")]
+    OrderNotTriggered,
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
+    #[msg("Invalid DCA schedule")]
+    InvalidDcaSchedule,
+    #[msg("Slippage must be below 10000 bps")]
+    InvalidSlippage,
+    #[msg("DCA cycle is not due yet")]
+    DcaNotDue,
+    #[msg("Price feed does not match the traded mint")]
+    InvalidPriceFeed,
+    #[msg("Output is outside the allowed slippage bounds")]
+    SlippageExceeded,
+    #[msg("Fee exceeds the allowed maximum")]
+    FeeTooHigh,
+    #[msg("Leader has no new trade to mirror")]
+    NothingToMirror,
+    #[msg("Follower missed leader trades and must be resynced")]
+    MirrorGap,
+    #[msg("Follower holds the maximum number of positions")]
+    TooManyPositions,
+    #[msg("Invalid lending pool parameters")]
+    InvalidLendingParams,
+    #[msg("Insufficient liquidity in lending pool")]
+    InsufficientLiquidity,
+    #[msg("Borrow would exceed the maximum loan-to-value")]
+    InsufficientCollateral,
+    #[msg("Position is not eligible for liquidation")]
+    PositionHealthy,
+    #[msg("Agent owes the lending pool; repay the loan first")]
+    DebtOutstanding,
+    #[msg("Sub-agent still has deposits, lending positions, orders, DCA schedules or followers")]
+    SubagentBusy,
+    #[msg("Invalid risk configuration")]
+    InvalidRiskConfig,
+    #[msg("Trade exceeds the agent's risk limits")]
+    RiskLimitExceeded,
+    #[msg("Invalid governance configuration")]
+    InvalidGovernanceConfig,
+    #[msg("Voting tokens are locked until voting ends")]
+    VotingTokensLocked,
+    #[msg("Escrowed tokens below the proposal threshold")]
+    BelowProposalThreshold,
+    #[msg("Invalid proposal")]
+    InvalidProposal,
+    #[msg("Voting period has ended")]
+    VotingClosed,
+    #[msg("Proposal has already been executed")]
+    ProposalAlreadyExecuted,
+    #[msg("Proposal timelock has not elapsed")]
+    ProposalNotReady,
+    #[msg("Proposal did not pass")]
+    ProposalNotPassed,
+    #[msg("Proposal can still pass or be executed")]
+    ProposalActive,
+    #[msg("Tokens escrowed after the proposal was created cannot vote on it")]
+    VotingWeightTooNew,
+    #[msg("Agent was paused by governance and only a proposal can resume it")]
+    GovernancePaused,
+    #[msg("Agent has been retired")]
+    AgentRetired,
+    #[msg("Mint is not on the agent's trading whitelist")]
+    MintNotWhitelisted,
+    #[msg("Invalid configuration change")]
+    InvalidConfigChange,
+    #[msg("Configuration change delay has not elapsed")]
+    ConfigChangeNotReady,
+    #[msg("No circuit breaker threshold has been breached")]
+    CircuitBreakerNotTriggered,
+    #[msg("Circuit breaker is not tripped")]
+    CircuitBreakerNotTripped,
+    #[msg("Circuit breaker cooldown has not elapsed")]
+    CooldownActive,
+    #[msg("Emergency exit opens only after the agent has been inactive for the grace period")]
+    EmergencyExitClosed,
+    #[msg("Nothing to withdraw")]
+    NothingToWithdraw,
+    #[msg("Buyback pool is empty")]
+    NothingToBuyBack,
+    #[msg("Signer is not the platform revenue publisher")]
+    UnauthorizedPublisher,
+    #[msg("Invalid revenue epoch")]
+    InvalidRevenueEpoch,
+    #[msg("Revenue epoch has expired")]
+    RevenueEpochExpired,
+    #[msg("Revenue epoch has not expired yet")]
+    RevenueEpochActive,
+    #[msg("Invalid merkle proof")]
+    InvalidProof,
+    #[msg("Entitlement already claimed")]
+    AlreadyClaimed,
+    #[msg("Account is already on the current layout")]
+    AlreadyMigrated,
+    #[msg("Account cannot be migrated")]
+    InvalidMigrationAccount,
+    #[msg("Name must be between 1 and 32 characters")]
+    InvalidName,
+    #[msg("Sub-agents cannot spawn sub-agents")]
+    NestedSubagent,
+    #[msg("Agent is not a sub-agent of this parent")]
+    NotASubagent,
+    #[msg("Sub-agents inherit their parent's risk limits")]
+    InheritedRiskLimits,
+    #[msg("Royalty exceeds the maximum")]
+    InvalidRoyalty,
+    #[msg("Subscription period must be positive")]
+    InvalidLicenseModel,
+    #[msg("Strategy is not available for licensing")]
+    StrategyInactive,
+    #[msg("Perpetual licenses cannot be renewed")]
+    PerpetualLicense,
+    #[msg("Strategy license has expired")]
+    LicenseExpired,
+    #[msg("Strategy royalties must be paid first")]
+    RoyaltiesOutstanding,
+    #[msg("No strategy royalties owed")]
+    NoRoyaltiesOwed,
+    #[msg("Only the program upgrade authority can do this")]
+    UnauthorizedAdmin,
 }
@@ -1,8 +1,56 @@
 use anchor_lang::prelude::*;
 use anchor_lang::system_program;
+use anchor_lang::Discriminator;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use agent_manager::AgentState;
 
 declare_id!("AgentReg1stry1111111111111111111111111111111");
 
+/// Current `AgentRegistry` layout version
+pub const AGENT_REGISTRY_VERSION: u8 = 1;
+
+/// Maximum number of authorized revenue depositors
+pub const MAX_REVENUE_DEPOSITORS: usize = 16;
+
+/// Maximum length of a deposit memo
+pub const MAX_MEMO_LEN: usize = 64;
+
+/// Basis points in 100%
+pub const MAX_BPS: u16 = 10_000;
+
+/// Maximum number of beneficiaries in a revenue split
+pub const MAX_BENEFICIARIES: usize = 8;
+
+/// Split entries kept, including removed beneficiaries with unclaimed revenue
+pub const MAX_SPLIT_ENTRIES: usize = 16;
+
+/// Delay before a proposed revenue split can be applied
+pub const REVENUE_SPLIT_TIMELOCK: i64 = 2 * 24 * 60 * 60;
+
+/// Upper bound on the marketplace fee
+pub const MAX_MARKETPLACE_FEE_BPS: u16 = 1_000;
+
+/// Maximum length of an agent's metadata URI
+pub const MAX_URI_LEN: usize = 200;
+
+/// Longest cooldown a closed agent's id can be held for
+pub const MAX_TOMBSTONE_COOLDOWN: i64 = 365 * 24 * 60 * 60;
+
+/// `AgentRegistry::child_accounts` flags for accounts that must be closed
+/// along with the entry, so a re-registered id starts clean
+pub const CHILD_LINK: u8 = 1 << 0;
+pub const CHILD_METADATA: u8 = 1 << 1;
+pub const CHILD_REVENUE_SPLIT: u8 = 1 << 2;
+pub const CHILD_LISTING: u8 = 1 << 3;
+
+/// Longest agent id. Ids are lowercase ASCII letters, digits and dashes, so
+/// the id used in the PDA seeds is already its canonical form.
+pub const MAX_AGENT_ID_LEN: usize = 32;
+
+/// Maximum number of tags on an agent, and the length of each
+pub const MAX_TAGS: usize = 5;
+pub const MAX_TAG_LEN: usize = 32;
+
 #[program]
 pub mod agent_registry {
     use super::*;
@@ -13,10 +61,13 @@ pub mod agent_registry {
         agent_id: String,
         name: String,
     ) -> Result<()> {
-        require!(agent_id.len() <= 32, ErrorCode::AgentIdTooLong);
+        validate_agent_id(&agent_id)?;
         require!(name.len() <= 64, ErrorCode::NameTooLong);
+        require!(ctx.accounts.tombstone.data_is_empty(), ErrorCode::AgentIdTombstoned);
+        check_reserved_name(&ctx.accounts.reserved_name, &ctx.accounts.owner.key())?;
 
         let registry = &mut ctx.accounts.registry;
+        registry.version = AGENT_REGISTRY_VERSION;
         registry.agent_id = agent_id;
         registry.name = name;
         registry.owner = ctx.accounts.owner.key();
@@ -24,13 +75,146 @@ pub mod agent_registry {
         registry.total_revenue = 0;
         registry.claimed_revenue = 0;
         registry.bump = ctx.bumps.registry;
+        registry.total_tips = 0;
+        registry.claimed_tips = 0;
+        registry.has_revenue_split = false;
+        registry.pending_owner = Pubkey::default();
+        registry.attestation_counts = [0; 4];
+        registry.child_accounts = 0;
+        registry.token_ledger_count = 0;
+        registry.attestation_accounts = 0;
+        registry.reserved = [0; 3];
+
+        Ok(())
+    }
+
+    /// Register an agent linked to its agent-manager `AgentState`, which must
+    /// exist and be controlled by the registering owner
+    pub fn register_linked_agent(
+        ctx: Context<RegisterLinkedAgent>,
+        agent_id: String,
+        name: String,
+    ) -> Result<()> {
+        validate_agent_id(&agent_id)?;
+        require!(name.len() <= 64, ErrorCode::NameTooLong);
+        require!(ctx.accounts.tombstone.data_is_empty(), ErrorCode::AgentIdTombstoned);
+        check_reserved_name(&ctx.accounts.reserved_name, &ctx.accounts.owner.key())?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let owner = ctx.accounts.owner.key();
+        ctx.accounts.registry.set_inner(AgentRegistry::new(
+            agent_id,
+            name,
+            owner,
+            now,
+            ctx.bumps.registry,
+        ));
+        ctx.accounts.registry.child_accounts |= CHILD_LINK;
+
+        let agent_state = &ctx.accounts.agent_state;
+        ctx.accounts.link.set_inner(AgentLink {
+            registry: ctx.accounts.registry.key(),
+            agent_state: agent_state.key(),
+            token_mint: agent_state.token_mint,
+            source: CreationSource::Manual,
+            linked_at: now,
+            bump: ctx.bumps.link,
+        });
+
+        emit!(AgentLinkedEvent {
+            registry: ctx.accounts.registry.key(),
+            agent_state: agent_state.key(),
+            token_mint: agent_state.token_mint,
+            source: CreationSource::Manual,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Register an agent launched through the factory. Only the factory
+    /// configured in the registry config can sign. The agent state must be the
+    /// owner's agent-manager state, and its mint is recorded on the link.
+    pub fn register_factory_agent(
+        ctx: Context<RegisterFactoryAgent>,
+        agent_id: String,
+        name: String,
+    ) -> Result<()> {
+        validate_agent_id(&agent_id)?;
+        require!(name.len() <= 64, ErrorCode::NameTooLong);
+        require!(ctx.accounts.tombstone.data_is_empty(), ErrorCode::AgentIdTombstoned);
+        check_reserved_name(&ctx.accounts.reserved_name, &ctx.accounts.owner.key())?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let agent_state = ctx.accounts.agent_state.key();
+        let token_mint = ctx.accounts.agent_state.token_mint;
+        ctx.accounts.registry.set_inner(AgentRegistry::new(
+            agent_id,
+            name,
+            ctx.accounts.owner.key(),
+            now,
+            ctx.bumps.registry,
+        ));
+        ctx.accounts.registry.child_accounts |= CHILD_LINK;
+        ctx.accounts.link.set_inner(AgentLink {
+            registry: ctx.accounts.registry.key(),
+            agent_state,
+            token_mint,
+            source: CreationSource::Factory,
+            linked_at: now,
+            bump: ctx.bumps.link,
+        });
+
+        emit!(AgentLinkedEvent {
+            registry: ctx.accounts.registry.key(),
+            agent_state,
+            token_mint,
+            source: CreationSource::Factory,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Link an existing registry entry to its agent-manager `AgentState`
+    pub fn link_agent_state(ctx: Context<LinkAgentState>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let agent_state = &ctx.accounts.agent_state;
+        let registry_key = ctx.accounts.registry.key();
+
+        ctx.accounts.link.set_inner(AgentLink {
+            registry: registry_key,
+            agent_state: agent_state.key(),
+            token_mint: agent_state.token_mint,
+            source: CreationSource::Manual,
+            linked_at: now,
+            bump: ctx.bumps.link,
+        });
+
+        ctx.accounts.registry.child_accounts |= CHILD_LINK;
+
+        if let Some(metadata) = ctx.accounts.metadata.as_mut() {
+            metadata.apply_link(&ctx.accounts.link);
+            metadata.updated_at = now;
+        }
+
+        emit!(AgentLinkedEvent {
+            registry: registry_key,
+            agent_state: agent_state.key(),
+            token_mint: agent_state.token_mint,
+            source: CreationSource::Manual,
+            timestamp: now,
+        });
 
         Ok(())
     }
 
     /// Claim accumulated revenue (platform deposits revenue here)
     pub fn claim_revenue(ctx: Context<ClaimRevenue>) -> Result<()> {
-        let registry = &mut ctx.accounts.registry;
+        let registry = &ctx.accounts.registry;
+        require!(!registry.has_revenue_split, ErrorCode::RevenueSplitActive);
+        // Revenue on a listed agent is priced into the sale
+        require!(registry.child_accounts & CHILD_LISTING == 0, ErrorCode::AgentListed);
 
         // Calculate claimable amount (total - already claimed)
         let claimable = registry
@@ -41,142 +225,3489 @@ pub mod agent_registry {
         require!(claimable > 0, ErrorCode::NoRevenueToClaim);
 
         // Transfer SOL from registry PDA to owner
-        let registry_lamports = ctx.accounts.registry.to_account_info().lamports();
-        require!(registry_lamports >= claimable, ErrorCode::InsufficientBalance);
+        let registry_info = ctx.accounts.registry.to_account_info();
+        require!(registry_info.lamports() >= claimable, ErrorCode::InsufficientBalance);
 
-        **ctx.accounts.registry.to_account_info().try_borrow_mut_lamports()? -= claimable;
+        **registry_info.try_borrow_mut_lamports()? -= claimable;
         **ctx.accounts.owner.to_account_info().try_borrow_mut_lamports()? += claimable;
 
         // Update claimed amount
+        let registry = &mut ctx.accounts.registry;
         registry.claimed_revenue = registry.total_revenue;
 
         Ok(())
     }
 
-    /// Transfer agent ownership
+    /// Start transferring agent ownership. The new owner takes over once it
+    /// accepts; a PDA owner (DAO, multisig) accepts by signing through CPI.
     pub fn transfer_ownership(
         ctx: Context<TransferOwnership>,
         new_owner: Pubkey,
     ) -> Result<()> {
         let registry = &mut ctx.accounts.registry;
-        registry.owner = new_owner;
+        require!(
+            new_owner != Pubkey::default() && new_owner != registry.owner,
+            ErrorCode::InvalidNewOwner
+        );
+        registry.pending_owner = new_owner;
+
+        emit!(OwnershipTransferStartedEvent {
+            registry: registry.key(),
+            owner: registry.owner,
+            pending_owner: new_owner,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Complete a pending ownership transfer
+    pub fn accept_ownership(ctx: Context<AcceptOwnership>) -> Result<()> {
+        let registry = &mut ctx.accounts.registry;
+        let previous_owner = registry.owner;
+        registry.owner = registry.pending_owner;
+        registry.pending_owner = Pubkey::default();
+
+        emit!(OwnershipTransferredEvent {
+            registry: registry.key(),
+            previous_owner,
+            new_owner: registry.owner,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Withdraw a pending ownership transfer
+    pub fn cancel_transfer(ctx: Context<TransferOwnership>) -> Result<()> {
+        let registry = &mut ctx.accounts.registry;
+        require!(
+            registry.pending_owner != Pubkey::default(),
+            ErrorCode::NoPendingTransfer
+        );
+        let pending_owner = registry.pending_owner;
+        registry.pending_owner = Pubkey::default();
+
+        emit!(OwnershipTransferCancelledEvent {
+            registry: registry.key(),
+            owner: registry.owner,
+            pending_owner,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
         Ok(())
     }
 
-    /// Deposit revenue (platform backend calls this after trades)
+    /// Deposit revenue (platform backend calls this after trades). Deposits
+    /// from signers outside the config's depositor list are kept apart as tips
+    /// so they can't inflate `total_revenue`.
     pub fn deposit_revenue(
         ctx: Context<DepositRevenue>,
         amount: u64,
+        memo: String,
     ) -> Result<()> {
-        // Transfer SOL from platform to registry PDA
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(memo.len() <= MAX_MEMO_LEN, ErrorCode::MemoTooLong);
+
+        // Transfer SOL from depositor to registry PDA
         system_program::transfer(
             CpiContext::new(
                 ctx.accounts.system_program.to_account_info(),
                 system_program::Transfer {
-                    from: ctx.accounts.platform.to_account_info(),
+                    from: ctx.accounts.depositor.to_account_info(),
                     to: ctx.accounts.registry.to_account_info(),
                 },
             ),
             amount,
         )?;
 
-        // Update total revenue
+        let depositor = ctx.accounts.depositor.key();
+        let trusted = ctx.accounts.config.is_depositor(&depositor);
+
         let registry = &mut ctx.accounts.registry;
-        registry.total_revenue = registry
-            .total_revenue
-            .checked_add(amount)
+        if trusted {
+            registry.total_revenue = registry
+                .total_revenue
+                .checked_add(amount)
+                .ok_or(ErrorCode::Overflow)?;
+        } else {
+            registry.total_tips = registry
+                .total_tips
+                .checked_add(amount)
+                .ok_or(ErrorCode::Overflow)?;
+        }
+
+        emit!(RevenueDepositedEvent {
+            registry: registry.key(),
+            agent_id: registry.agent_id.clone(),
+            depositor,
+            amount,
+            trusted,
+            memo,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Claim tips deposited by signers outside the depositor list
+    pub fn claim_tips(ctx: Context<ClaimTips>) -> Result<()> {
+        let registry = &ctx.accounts.registry;
+        require!(registry.child_accounts & CHILD_LISTING == 0, ErrorCode::AgentListed);
+        let claimable = registry
+            .total_tips
+            .checked_sub(registry.claimed_tips)
+            .ok_or(ErrorCode::InsufficientRevenue)?;
+        require!(claimable > 0, ErrorCode::NoRevenueToClaim);
+
+        let registry_info = ctx.accounts.registry.to_account_info();
+        require!(registry_info.lamports() >= claimable, ErrorCode::InsufficientBalance);
+
+        **registry_info.try_borrow_mut_lamports()? -= claimable;
+        **ctx.accounts.owner.to_account_info().try_borrow_mut_lamports()? += claimable;
+
+        let registry = &mut ctx.accounts.registry;
+        registry.claimed_tips = registry.total_tips;
+
+        Ok(())
+    }
+
+    /// Create the agent's revenue split account, with no beneficiaries until
+    /// a proposed split is applied
+    pub fn create_revenue_split(ctx: Context<CreateRevenueSplit>) -> Result<()> {
+        let split = &mut ctx.accounts.split;
+        split.registry = ctx.accounts.registry.key();
+        split.beneficiaries = Vec::new();
+        split.pending = Vec::new();
+        split.pending_eta = 0;
+        split.bump = ctx.bumps.split;
+
+        ctx.accounts.registry.child_accounts |= CHILD_REVENUE_SPLIT;
+
+        Ok(())
+    }
+
+    /// Queue a new set of beneficiaries, applied after the timelock
+    pub fn propose_revenue_split(
+        ctx: Context<UpdateRevenueSplit>,
+        shares: Vec<BeneficiaryShare>,
+    ) -> Result<()> {
+        require!(
+            !shares.is_empty() && shares.len() <= MAX_BENEFICIARIES,
+            ErrorCode::InvalidRevenueSplit
+        );
+        require!(
+            shares.iter().all(|share| share.bps > 0)
+                && shares.iter().map(|share| share.bps as u32).sum::<u32>() == MAX_BPS as u32,
+            ErrorCode::InvalidRevenueSplit
+        );
+        for (i, share) in shares.iter().enumerate() {
+            require!(
+                shares[i + 1..].iter().all(|other| other.wallet != share.wallet),
+                ErrorCode::InvalidRevenueSplit
+            );
+        }
+
+        let now = Clock::get()?.unix_timestamp;
+        let split = &mut ctx.accounts.split;
+        split.pending = shares;
+        split.pending_eta = now
+            .checked_add(REVENUE_SPLIT_TIMELOCK)
             .ok_or(ErrorCode::Overflow)?;
 
+        emit!(RevenueSplitProposedEvent {
+            registry: split.registry,
+            shares: split.pending.clone(),
+            eta: split.pending_eta,
+            timestamp: now,
+        });
+
         Ok(())
     }
-}
 
-#[derive(Accounts)]
-#[instruction(agent_id: String)]
-pub struct RegisterAgent<'info> {
-    #[account(
-        init,
-        payer = owner,
-        space = 8 + AgentRegistry::INIT_SPACE,
-        seeds = [b"agent", agent_id.as_bytes()],
-        bump
-    )]
-    pub registry: Account<'info, AgentRegistry>,
+    /// Drop a proposed split before it is applied
+    pub fn cancel_revenue_split(ctx: Context<UpdateRevenueSplit>) -> Result<()> {
+        let split = &mut ctx.accounts.split;
+        require!(!split.pending.is_empty(), ErrorCode::NoPendingSplit);
 
-    #[account(mut)]
-    pub owner: Signer<'info>,
+        split.pending = Vec::new();
+        split.pending_eta = 0;
 
-    pub system_program: Program<'info, System>,
-}
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct ClaimRevenue<'info> {
-    #[account(
-        mut,
-        seeds = [b"agent", registry.agent_id.as_bytes()],
-        bump = registry.bump,
-        has_one = owner
-    )]
-    pub registry: Account<'info, AgentRegistry>,
+    /// Apply the proposed split once its timelock has passed. Revenue accrued
+    /// so far is settled to the current beneficiaries first, in SOL and in
+    /// every token ledger, which are all passed in `remaining_accounts`.
+    pub fn apply_revenue_split<'info>(
+        ctx: Context<'_, '_, 'info, 'info, UpdateRevenueSplit<'info>>,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let split = &mut ctx.accounts.split;
+        require!(!split.pending.is_empty(), ErrorCode::NoPendingSplit);
+        require!(now >= split.pending_eta, ErrorCode::TimelockNotExpired);
 
-    #[account(mut)]
-    pub owner: Signer<'info>,
-}
+        let registry = &mut ctx.accounts.registry;
+        let ledger_accounts = ctx.remaining_accounts;
+        require!(
+            ledger_accounts.len() == registry.token_ledger_count as usize,
+            ErrorCode::MissingTokenAccounts
+        );
+        let mut mints = Vec::with_capacity(ledger_accounts.len());
+        for info in ledger_accounts.iter() {
+            let mut ledger = Account::<TokenRevenueLedger>::try_from(info)?;
+            require!(
+                info.is_writable
+                    && ledger.registry == registry.key()
+                    && !mints.contains(&ledger.mint),
+                ErrorCode::MissingTokenAccounts
+            );
+            mints.push(ledger.mint);
 
-#[derive(Accounts)]
-pub struct TransferOwnership<'info> {
-    #[account(
-        mut,
-        seeds = [b"agent", registry.agent_id.as_bytes()],
-        bump = registry.bump,
-        has_one = owner
-    )]
-    pub registry: Account<'info, AgentRegistry>,
+            if registry.has_revenue_split {
+                ledger.settle_split(split)?;
+            } else {
+                // Token revenue from before the split belongs to the owner alone
+                require!(
+                    ledger.claimed_revenue == ledger.total_revenue,
+                    ErrorCode::UnclaimedRevenue
+                );
+            }
+            ledger.split_checkpoint = ledger.total_revenue;
+            ledger.split_accruals.retain(|accrual| accrual.accrued > 0);
+            ledger.exit(&crate::ID)?;
+        }
 
-    pub owner: Signer<'info>,
-}
+        if !registry.has_revenue_split {
+            require!(registry.child_accounts & CHILD_LISTING == 0, ErrorCode::AgentListed);
+            // Revenue deposited before the split belongs to the owner alone
+            require!(
+                registry.claimed_revenue == registry.total_revenue,
+                ErrorCode::UnclaimedRevenue
+            );
+            registry.has_revenue_split = true;
+        }
 
-#[derive(Accounts)]
-pub struct DepositRevenue<'info> {
-    #[account(
-        mut,
-        seeds = [b"agent", registry.agent_id.as_bytes()],
-        bump = registry.bump
-    )]
-    pub registry: Account<'info, AgentRegistry>,
+        let total_revenue = registry.total_revenue;
+        for beneficiary in split.beneficiaries.iter_mut() {
+            beneficiary.settle(total_revenue)?;
+            beneficiary.bps = 0;
+        }
 
-    #[account(mut)]
-    pub platform: Signer<'info>,
+        let pending = std::mem::take(&mut split.pending);
+        for share in pending.iter() {
+            match split
+                .beneficiaries
+                .iter_mut()
+                .find(|beneficiary| beneficiary.wallet == share.wallet)
+            {
+                Some(beneficiary) => beneficiary.bps = share.bps,
+                None => split.beneficiaries.push(Beneficiary {
+                    wallet: share.wallet,
+                    bps: share.bps,
+                    checkpoint: total_revenue,
+                    accrued: 0,
+                }),
+            }
+        }
+        split
+            .beneficiaries
+            .retain(|beneficiary| beneficiary.bps > 0 || beneficiary.accrued > 0);
+        require!(
+            split.beneficiaries.len() <= MAX_SPLIT_ENTRIES,
+            ErrorCode::TooManySplitEntries
+        );
+        split.pending_eta = 0;
 
-    pub system_program: Program<'info, System>,
-}
+        emit!(RevenueSplitAppliedEvent {
+            registry: registry.key(),
+            shares: pending,
+            timestamp: now,
+        });
 
-#[account]
-#[derive(InitSpace)]
-pub struct AgentRegistry {
-    #[max_len(32)]
-    pub agent_id: String,
-    #[max_len(64)]
-    pub name: String,
-    pub owner: Pubkey,
-    pub created_at: i64,
-    pub total_revenue: u64,
-    pub claimed_revenue: u64,
-    pub bump: u8,
-}
+        Ok(())
+    }
 
-#[error_code]
-pub enum ErrorCode {
-    #[msg("Agent ID cannot be longer than 32 characters")]
-    AgentIdTooLong,
-    #[msg("Name cannot be longer than 64 characters")]
-    NameTooLong,
-    #[msg("Insufficient revenue to claim")]
-    InsufficientRevenue,
-    #[msg("No revenue available to claim")]
-    NoRevenueToClaim,
-    #[msg("Insufficient balance in registry")]
-    InsufficientBalance,
-    #[msg("Arithmetic overflow")]
-    Overflow,
+    /// Claim a beneficiary's share of the revenue accrued since its last claim
+    pub fn claim_split_revenue(ctx: Context<ClaimSplitRevenue>) -> Result<()> {
+        pay_split_share(
+            &mut ctx.accounts.registry,
+            &mut ctx.accounts.split,
+            &ctx.accounts.beneficiary.to_account_info(),
+            false,
+        )
+    }
+
+    /// Pay out a removed beneficiary that hasn't claimed, freeing its entry
+    /// so the owner can keep changing the split
+    pub fn settle_removed_beneficiary(ctx: Context<SettleRemovedBeneficiary>) -> Result<()> {
+        pay_split_share(
+            &mut ctx.accounts.registry,
+            &mut ctx.accounts.split,
+            &ctx.accounts.beneficiary.to_account_info(),
+            true,
+        )
+    }
+
+    /// Open the revenue ledger and registry-owned token account for a mint
+    pub fn open_token_ledger(ctx: Context<OpenTokenLedger>) -> Result<()> {
+        let ledger = &mut ctx.accounts.ledger;
+        ledger.registry = ctx.accounts.registry.key();
+        ledger.mint = ctx.accounts.mint.key();
+        ledger.vault = ctx.accounts.vault.key();
+        ledger.total_revenue = 0;
+        ledger.claimed_revenue = 0;
+        ledger.total_tips = 0;
+        ledger.claimed_tips = 0;
+        ledger.split_checkpoint = 0;
+        ledger.split_accruals = Vec::new();
+        ledger.bump = ctx.bumps.ledger;
+
+        let registry = &mut ctx.accounts.registry;
+        registry.token_ledger_count = registry
+            .token_ledger_count
+            .checked_add(1)
+            .ok_or(ErrorCode::Overflow)?;
+
+        Ok(())
+    }
+
+    /// Deposit SPL token revenue into the registry's account for the mint.
+    /// Unlisted depositors are tracked as tips, as for SOL deposits. Under a
+    /// revenue split, trusted deposits are shared out like SOL revenue.
+    pub fn deposit_revenue_token(
+        ctx: Context<DepositRevenueToken>,
+        amount: u64,
+        memo: String,
+    ) -> Result<()> {
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(memo.len() <= MAX_MEMO_LEN, ErrorCode::MemoTooLong);
+
+        let depositor = ctx.accounts.depositor.key();
+        let trusted = ctx.accounts.config.is_depositor(&depositor);
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.depositor_token_account.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                    authority: ctx.accounts.depositor.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let ledger = &mut ctx.accounts.ledger;
+        if trusted {
+            ledger.total_revenue = ledger
+                .total_revenue
+                .checked_add(amount)
+                .ok_or(ErrorCode::Overflow)?;
+        } else {
+            ledger.total_tips = ledger
+                .total_tips
+                .checked_add(amount)
+                .ok_or(ErrorCode::Overflow)?;
+        }
+
+        emit!(TokenRevenueDepositedEvent {
+            registry: ctx.accounts.registry.key(),
+            mint: ledger.mint,
+            depositor,
+            amount,
+            trusted,
+            memo,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Claim the token revenue and tips accrued for a mint. Under a revenue
+    /// split the owner only takes tips and the rounding left over once every
+    /// beneficiary's share is settled.
+    pub fn claim_revenue_token(ctx: Context<ClaimRevenueToken>) -> Result<()> {
+        require!(
+            ctx.accounts.registry.child_accounts & CHILD_LISTING == 0,
+            ErrorCode::AgentListed
+        );
+
+        let ledger = &mut ctx.accounts.ledger;
+        let mut owed_to_split = 0u64;
+        if ctx.accounts.registry.has_revenue_split {
+            let split = ctx.accounts.split.as_ref().ok_or(ErrorCode::MissingChildAccount)?;
+            ledger.settle_split(split)?;
+            owed_to_split = ledger
+                .split_accruals
+                .iter()
+                .try_fold(0u64, |owed, accrual| owed.checked_add(accrual.accrued))
+                .ok_or(ErrorCode::Overflow)?;
+        }
+
+        let revenue = ledger
+            .total_revenue
+            .checked_sub(ledger.claimed_revenue)
+            .and_then(|revenue| revenue.checked_sub(owed_to_split))
+            .ok_or(ErrorCode::InsufficientRevenue)?;
+        let claimable = ledger
+            .total_tips
+            .checked_sub(ledger.claimed_tips)
+            .and_then(|tips| revenue.checked_add(tips))
+            .ok_or(ErrorCode::InsufficientRevenue)?;
+        require!(claimable > 0, ErrorCode::NoRevenueToClaim);
+        require!(ctx.accounts.vault.amount >= claimable, ErrorCode::InsufficientBalance);
+
+        let registry = &ctx.accounts.registry;
+        let seeds = &[b"agent", registry.agent_id.as_bytes(), &[registry.bump]];
+        let signer = &[&seeds[..]];
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.vault.to_account_info(),
+                    to: ctx.accounts.owner_token_account.to_account_info(),
+                    authority: ctx.accounts.registry.to_account_info(),
+                },
+                signer,
+            ),
+            claimable,
+        )?;
+
+        let ledger = &mut ctx.accounts.ledger;
+        ledger.claimed_revenue = ledger.total_revenue - owed_to_split;
+        ledger.claimed_tips = ledger.total_tips;
+
+        emit!(TokenRevenueClaimedEvent {
+            registry: ctx.accounts.registry.key(),
+            mint: ledger.mint,
+            owner: ctx.accounts.owner.key(),
+            amount: claimable,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Claim a beneficiary's share of a mint's revenue accrued since its last claim
+    pub fn claim_split_revenue_token(ctx: Context<ClaimSplitRevenueToken>) -> Result<()> {
+        pay_token_split_share(
+            &ctx.accounts.registry,
+            &ctx.accounts.split,
+            &mut ctx.accounts.ledger,
+            &ctx.accounts.vault,
+            &ctx.accounts.beneficiary_token_account,
+            &ctx.accounts.token_program,
+            ctx.accounts.beneficiary.key(),
+            false,
+        )
+    }
+
+    /// Pay out a removed beneficiary's unclaimed share of a mint's revenue,
+    /// freeing its entry in the ledger
+    pub fn settle_removed_beneficiary_token(
+        ctx: Context<SettleRemovedBeneficiaryToken>,
+    ) -> Result<()> {
+        pay_token_split_share(
+            &ctx.accounts.registry,
+            &ctx.accounts.split,
+            &mut ctx.accounts.ledger,
+            &ctx.accounts.vault,
+            &ctx.accounts.beneficiary_token_account,
+            &ctx.accounts.token_program,
+            ctx.accounts.beneficiary.key(),
+            true,
+        )
+    }
+
+    /// Attach discovery metadata to an agent and add it to the category and
+    /// owner indexes
+    pub fn create_metadata(
+        ctx: Context<CreateMetadata>,
+        category: AgentCategory,
+        uri: String,
+        tags: Vec<String>,
+        agent_state: Pubkey,
+        token_mint: Pubkey,
+    ) -> Result<()> {
+        validate_metadata(&uri, &tags)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let registry_key = ctx.accounts.registry.key();
+        let owner = ctx.accounts.owner.key();
+        let payer = ctx.accounts.payer.key();
+
+        let metadata = &mut ctx.accounts.metadata;
+        metadata.registry = registry_key;
+        metadata.category = category;
+        metadata.source = CreationSource::Manual;
+        metadata.linked = false;
+        metadata.agent_state = agent_state;
+        metadata.token_mint = token_mint;
+        metadata.uri = uri;
+        metadata.tags = tags;
+        metadata.updated_at = now;
+        metadata.bump = ctx.bumps.metadata;
+
+        // A verified link takes precedence over the declared accounts
+        if let Some(link) = ctx.accounts.link.as_ref() {
+            metadata.apply_link(link);
+        }
+
+        ctx.accounts.category_index.set_inner(CategoryIndexEntry {
+            category,
+            registry: registry_key,
+            rent_payer: payer,
+            bump: ctx.bumps.category_index,
+        });
+        ctx.accounts.owner_index.set_inner(OwnerIndexEntry {
+            owner,
+            registry: registry_key,
+            rent_payer: payer,
+            bump: ctx.bumps.owner_index,
+        });
+        ctx.accounts.registry.child_accounts |= CHILD_METADATA;
+
+        emit!(MetadataUpdatedEvent {
+            registry: registry_key,
+            category,
+            uri: metadata.uri.clone(),
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Update an agent's URI, tags and linked accounts
+    pub fn update_metadata(
+        ctx: Context<UpdateMetadata>,
+        uri: String,
+        tags: Vec<String>,
+        agent_state: Pubkey,
+        token_mint: Pubkey,
+    ) -> Result<()> {
+        validate_metadata(&uri, &tags)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let metadata = &mut ctx.accounts.metadata;
+        require!(
+            !metadata.linked
+                || (metadata.agent_state == agent_state && metadata.token_mint == token_mint),
+            ErrorCode::LinkedAccountsImmutable
+        );
+        metadata.agent_state = agent_state;
+        metadata.token_mint = token_mint;
+        metadata.uri = uri;
+        metadata.tags = tags;
+        metadata.updated_at = now;
+
+        emit!(MetadataUpdatedEvent {
+            registry: metadata.registry,
+            category: metadata.category,
+            uri: metadata.uri.clone(),
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Move an agent to another category, swapping its category index entry
+    pub fn set_category(ctx: Context<SetCategory>, category: AgentCategory) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let registry_key = ctx.accounts.registry.key();
+
+        let metadata = &mut ctx.accounts.metadata;
+        require!(metadata.category != category, ErrorCode::CategoryUnchanged);
+        metadata.category = category;
+        metadata.updated_at = now;
+
+        ctx.accounts.new_category_index.set_inner(CategoryIndexEntry {
+            category,
+            registry: registry_key,
+            rent_payer: ctx.accounts.payer.key(),
+            bump: ctx.bumps.new_category_index,
+        });
+
+        emit!(MetadataUpdatedEvent {
+            registry: registry_key,
+            category,
+            uri: metadata.uri.clone(),
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Replace an owner index entry left behind by an ownership change.
+    /// Permissionless so indexers can keep the owner index current.
+    pub fn reindex_owner(ctx: Context<ReindexOwner>) -> Result<()> {
+        let registry = &ctx.accounts.registry;
+        require_keys_neq!(
+            ctx.accounts.stale_index.owner,
+            registry.owner,
+            ErrorCode::IndexUpToDate
+        );
+
+        ctx.accounts.owner_index.set_inner(OwnerIndexEntry {
+            owner: registry.owner,
+            registry: registry.key(),
+            rent_payer: ctx.accounts.payer.key(),
+            bump: ctx.bumps.owner_index,
+        });
+
+        Ok(())
+    }
+
+    /// Close a fully claimed token revenue ledger and its token account
+    pub fn close_token_ledger(ctx: Context<CloseTokenLedger>) -> Result<()> {
+        let ledger = &ctx.accounts.ledger;
+        require!(
+            ledger.claimed_revenue == ledger.total_revenue
+                && ledger.claimed_tips == ledger.total_tips
+                && ctx.accounts.vault.amount == 0,
+            ErrorCode::RevenueOutstanding
+        );
+
+        let registry = &ctx.accounts.registry;
+        let seeds = &[b"agent", registry.agent_id.as_bytes(), &[registry.bump]];
+        let signer = &[&seeds[..]];
+
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token::CloseAccount {
+                account: ctx.accounts.vault.to_account_info(),
+                destination: ctx.accounts.owner.to_account_info(),
+                authority: ctx.accounts.registry.to_account_info(),
+            },
+            signer,
+        ))?;
+
+        let registry = &mut ctx.accounts.registry;
+        registry.token_ledger_count = registry.token_ledger_count.saturating_sub(1);
+
+        Ok(())
+    }
+
+    /// Deregister an agent and reclaim its rent. Unclaimed SOL revenue and
+    /// tips are swept to the owner, except under a revenue split, where every
+    /// beneficiary must have claimed first. Token ledgers and attestations
+    /// must be closed, since their addresses derive from the registry's, and
+    /// any listing withdrawn. With a `cooldown`, a tombstone holds the
+    /// `agent_id` so nobody but the previous owner can take it until then.
+    pub fn close_agent(ctx: Context<CloseAgent>, cooldown: i64) -> Result<()> {
+        require!(
+            (0..=MAX_TOMBSTONE_COOLDOWN).contains(&cooldown),
+            ErrorCode::InvalidCooldown
+        );
+        require!(
+            (cooldown > 0) == ctx.accounts.tombstone.is_some(),
+            ErrorCode::InvalidCooldown
+        );
+
+        let registry = &ctx.accounts.registry;
+        let children = registry.child_accounts;
+        require!(children & CHILD_LISTING == 0, ErrorCode::AgentListed);
+        require!(registry.token_ledger_count == 0, ErrorCode::RevenueOutstanding);
+        require!(registry.attestation_accounts == 0, ErrorCode::AttestationsOpen);
+        require!(
+            children & CHILD_LINK == 0 || ctx.accounts.link.is_some(),
+            ErrorCode::MissingChildAccount
+        );
+        require!(
+            children & CHILD_METADATA == 0
+                || (ctx.accounts.metadata.is_some()
+                    && ctx.accounts.category_index.is_some()
+                    && ctx.accounts.owner_index.is_some()),
+            ErrorCode::MissingChildAccount
+        );
+        require!(
+            children & CHILD_REVENUE_SPLIT == 0 || ctx.accounts.split.is_some(),
+            ErrorCode::MissingChildAccount
+        );
+
+        if registry.has_revenue_split {
+            let total_revenue = registry.total_revenue;
+            let split = ctx.accounts.split.as_mut().ok_or(ErrorCode::MissingChildAccount)?;
+            for beneficiary in split.beneficiaries.iter_mut() {
+                beneficiary.settle(total_revenue)?;
+                require!(beneficiary.accrued == 0, ErrorCode::RevenueOutstanding);
+            }
+        }
+
+        let now = Clock::get()?.unix_timestamp;
+        let registry = &ctx.accounts.registry;
+        if let Some(tombstone) = ctx.accounts.tombstone.as_mut() {
+            tombstone.agent_id = registry.agent_id.clone();
+            tombstone.previous_owner = registry.owner;
+            tombstone.rent_payer = ctx.accounts.payer.key();
+            tombstone.closed_at = now;
+            tombstone.reusable_at = now + cooldown;
+            tombstone.bump = ctx.bumps.tombstone;
+        }
+
+        emit!(AgentClosedEvent {
+            registry: registry.key(),
+            agent_id: registry.agent_id.clone(),
+            owner: registry.owner,
+            reusable_at: now + cooldown,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Remove a tombstone, freeing its `agent_id`. The previous owner can do
+    /// this at any time, anyone else once the cooldown has passed.
+    pub fn clear_tombstone(ctx: Context<ClearTombstone>) -> Result<()> {
+        let tombstone = &ctx.accounts.tombstone;
+        require!(
+            ctx.accounts.caller.key() == tombstone.previous_owner
+                || Clock::get()?.unix_timestamp >= tombstone.reusable_at,
+            ErrorCode::AgentIdTombstoned
+        );
+
+        Ok(())
+    }
+
+    /// Create the registry config. Only the program's upgrade authority can,
+    /// and it becomes the admin.
+    pub fn initialize_registry_config(ctx: Context<InitializeRegistryConfig>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.admin = ctx.accounts.admin.key();
+        config.revenue_depositors = Vec::new();
+        config.treasury = ctx.accounts.admin.key();
+        config.marketplace_fee_bps = 0;
+        config.factory = Pubkey::default();
+        config.bump = ctx.bumps.config;
+
+        msg!("Registry config initialized with admin: {}", config.admin);
+        Ok(())
+    }
+
+    /// Authorize a signer to deposit revenue
+    pub fn add_revenue_depositor(
+        ctx: Context<UpdateRegistryConfig>,
+        depositor: Pubkey,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        require!(!config.is_depositor(&depositor), ErrorCode::DepositorAlreadyAuthorized);
+        require!(
+            config.revenue_depositors.len() < MAX_REVENUE_DEPOSITORS,
+            ErrorCode::TooManyDepositors
+        );
+
+        config.revenue_depositors.push(depositor);
+
+        msg!("Revenue depositor added: {}", depositor);
+        Ok(())
+    }
+
+    /// Set where marketplace fees go and how large they are
+    pub fn set_marketplace_config(
+        ctx: Context<UpdateRegistryConfig>,
+        treasury: Pubkey,
+        marketplace_fee_bps: u16,
+    ) -> Result<()> {
+        require!(
+            marketplace_fee_bps <= MAX_MARKETPLACE_FEE_BPS,
+            ErrorCode::InvalidMarketplaceFee
+        );
+
+        let config = &mut ctx.accounts.config;
+        config.treasury = treasury;
+        config.marketplace_fee_bps = marketplace_fee_bps;
+
+        msg!("Marketplace fee set to {} bps", marketplace_fee_bps);
+        Ok(())
+    }
+
+    /// Offer the agent for sale, priced in SOL (`currency` default) or an SPL mint
+    pub fn list_agent(
+        ctx: Context<ListAgent>,
+        price: u64,
+        currency: Pubkey,
+        revenue_policy: ListingRevenuePolicy,
+    ) -> Result<()> {
+        require!(price > 0, ErrorCode::InvalidAmount);
+        // Beneficiaries would otherwise be left to whatever split the buyer,
+        // or the seller before the sale, sets
+        require!(
+            !ctx.accounts.registry.has_revenue_split,
+            ErrorCode::RevenueSplitActive
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        let listing = &mut ctx.accounts.listing;
+        listing.registry = ctx.accounts.registry.key();
+        listing.seller = ctx.accounts.owner.key();
+        listing.price = price;
+        listing.currency = currency;
+        listing.revenue_policy = revenue_policy;
+        listing.rent_payer = ctx.accounts.payer.key();
+        listing.created_at = now;
+        listing.bump = ctx.bumps.listing;
+
+        ctx.accounts.registry.child_accounts |= CHILD_LISTING;
+
+        emit!(AgentListedEvent {
+            registry: listing.registry,
+            seller: listing.seller,
+            price,
+            currency,
+            revenue_policy: listing.revenue_policy.clone(),
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Withdraw the agent from sale
+    pub fn delist_agent(ctx: Context<DelistAgent>) -> Result<()> {
+        ctx.accounts.registry.child_accounts &= !CHILD_LISTING;
+
+        emit!(AgentDelistedEvent {
+            registry: ctx.accounts.registry.key(),
+            seller: ctx.accounts.owner.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Buy a listed agent. Payment to the seller and treasury and the change
+    /// of ownership happen in this one instruction, so neither side can be
+    /// left holding the other's half of the trade.
+    ///
+    /// Under `SettleToSeller`, every open token ledger is passed in
+    /// `remaining_accounts` as (ledger, vault, seller token account).
+    pub fn buy_agent<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BuyAgent<'info>>,
+        max_price: u64,
+    ) -> Result<()> {
+        let listing = &ctx.accounts.listing;
+        let registry = &ctx.accounts.registry;
+        // A listing outlives an ownership change made through transfer_ownership
+        require_keys_eq!(listing.seller, registry.owner, ErrorCode::StaleListing);
+        require!(!registry.has_revenue_split, ErrorCode::RevenueSplitActive);
+        require_keys_neq!(ctx.accounts.buyer.key(), registry.owner, ErrorCode::InvalidNewOwner);
+        require!(listing.price <= max_price, ErrorCode::PriceExceedsMax);
+
+        let price = listing.price;
+        let fee = (price as u128 * ctx.accounts.config.marketplace_fee_bps as u128
+            / MAX_BPS as u128) as u64;
+        let proceeds = price - fee;
+
+        if listing.currency == Pubkey::default() {
+            pay_sol(
+                &ctx.accounts.buyer.to_account_info(),
+                &ctx.accounts.seller.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+                proceeds,
+            )?;
+            pay_sol(
+                &ctx.accounts.buyer.to_account_info(),
+                &ctx.accounts.treasury.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+                fee,
+            )?;
+        } else {
+            let (Some(buyer_tokens), Some(seller_tokens), Some(treasury_tokens), Some(token_program)) = (
+                ctx.accounts.buyer_token_account.as_ref(),
+                ctx.accounts.seller_token_account.as_ref(),
+                ctx.accounts.treasury_token_account.as_ref(),
+                ctx.accounts.token_program.as_ref(),
+            ) else {
+                return err!(ErrorCode::MissingTokenAccounts);
+            };
+            require!(
+                buyer_tokens.mint == listing.currency
+                    && seller_tokens.mint == listing.currency
+                    && treasury_tokens.mint == listing.currency,
+                ErrorCode::MissingTokenAccounts
+            );
+            require_keys_eq!(seller_tokens.owner, listing.seller, ErrorCode::MissingTokenAccounts);
+            require_keys_eq!(
+                treasury_tokens.owner,
+                ctx.accounts.config.treasury,
+                ErrorCode::MissingTokenAccounts
+            );
+
+            for (to, amount) in [(seller_tokens, proceeds), (treasury_tokens, fee)] {
+                if amount > 0 {
+                    token::transfer(
+                        CpiContext::new(
+                            token_program.to_account_info(),
+                            Transfer {
+                                from: buyer_tokens.to_account_info(),
+                                to: to.to_account_info(),
+                                authority: ctx.accounts.buyer.to_account_info(),
+                            },
+                        ),
+                        amount,
+                    )?;
+                }
+            }
+        }
+
+        // Settle unclaimed revenue and tips, SOL and token, to the seller if
+        // the listing says so. Otherwise they move with the agent.
+        let mut settled = 0;
+        if listing.revenue_policy == ListingRevenuePolicy::SettleToSeller {
+            let ledger_accounts = ctx.remaining_accounts;
+            require!(
+                ledger_accounts.len() == 3 * registry.token_ledger_count as usize,
+                ErrorCode::MissingTokenAccounts
+            );
+            if !ledger_accounts.is_empty() {
+                let token_program = ctx
+                    .accounts
+                    .token_program
+                    .as_ref()
+                    .ok_or(ErrorCode::MissingTokenAccounts)?;
+                let seeds = &[b"agent", registry.agent_id.as_bytes(), &[registry.bump]];
+                let signer = &[&seeds[..]];
+
+                let mut mints = Vec::with_capacity(ledger_accounts.len() / 3);
+                for accounts in ledger_accounts.chunks(3) {
+                    let mut ledger = Account::<TokenRevenueLedger>::try_from(&accounts[0])?;
+                    let vault = Account::<TokenAccount>::try_from(&accounts[1])?;
+                    let seller_tokens = Account::<TokenAccount>::try_from(&accounts[2])?;
+                    require!(
+                        ledger.registry == registry.key()
+                            && ledger.vault == vault.key()
+                            && seller_tokens.owner == listing.seller
+                            && seller_tokens.mint == ledger.mint
+                            && !mints.contains(&ledger.mint),
+                        ErrorCode::MissingTokenAccounts
+                    );
+                    mints.push(ledger.mint);
+
+                    let amount = ledger
+                        .total_revenue
+                        .saturating_sub(ledger.claimed_revenue)
+                        .checked_add(ledger.total_tips.saturating_sub(ledger.claimed_tips))
+                        .ok_or(ErrorCode::Overflow)?;
+                    require!(vault.amount >= amount, ErrorCode::InsufficientBalance);
+                    if amount > 0 {
+                        token::transfer(
+                            CpiContext::new_with_signer(
+                                token_program.to_account_info(),
+                                Transfer {
+                                    from: vault.to_account_info(),
+                                    to: seller_tokens.to_account_info(),
+                                    authority: registry.to_account_info(),
+                                },
+                                signer,
+                            ),
+                            amount,
+                        )?;
+                    }
+
+                    ledger.claimed_revenue = ledger.total_revenue;
+                    ledger.claimed_tips = ledger.total_tips;
+                    ledger.exit(&crate::ID)?;
+                }
+            }
+
+            let registry = &mut ctx.accounts.registry;
+            settled += registry.total_revenue.saturating_sub(registry.claimed_revenue);
+            registry.claimed_revenue = registry.total_revenue;
+            settled += registry.total_tips.saturating_sub(registry.claimed_tips);
+            registry.claimed_tips = registry.total_tips;
+
+            if settled > 0 {
+                let registry_info = registry.to_account_info();
+                require!(registry_info.lamports() >= settled, ErrorCode::InsufficientBalance);
+                **registry_info.try_borrow_mut_lamports()? -= settled;
+                **ctx.accounts.seller.to_account_info().try_borrow_mut_lamports()? += settled;
+            }
+        }
+
+        let registry = &mut ctx.accounts.registry;
+        let seller = registry.owner;
+        registry.owner = ctx.accounts.buyer.key();
+        registry.pending_owner = Pubkey::default();
+        registry.child_accounts &= !CHILD_LISTING;
+
+        emit!(AgentSoldEvent {
+            registry: registry.key(),
+            seller,
+            buyer: registry.owner,
+            price,
+            currency: ctx.accounts.listing.currency,
+            fee,
+            revenue_settled: settled,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        emit!(OwnershipTransferredEvent {
+            registry: registry.key(),
+            previous_owner: seller,
+            new_owner: registry.owner,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Trust a factory state PDA to register the agents it launches
+    pub fn set_factory(ctx: Context<UpdateRegistryConfig>, factory: Pubkey) -> Result<()> {
+        ctx.accounts.config.factory = factory;
+
+        msg!("Registry factory set to: {}", factory);
+        Ok(())
+    }
+
+    /// Reserve an agent id so only `holder` can register it. A default
+    /// `holder` keeps the id from being registered at all.
+    pub fn reserve_name(ctx: Context<ReserveName>, name: String, holder: Pubkey) -> Result<()> {
+        validate_agent_id(&name)?;
+
+        let reserved = &mut ctx.accounts.reserved_name;
+        reserved.name = name;
+        reserved.holder = holder;
+        reserved.bump = ctx.bumps.reserved_name;
+
+        msg!("Agent id reserved: {}", reserved.name);
+        Ok(())
+    }
+
+    /// Release a reserved agent id back to first come, first served
+    pub fn release_name(ctx: Context<ReleaseName>) -> Result<()> {
+        msg!("Agent id released: {}", ctx.accounts.reserved_name.name);
+        Ok(())
+    }
+
+    /// Allow an authority (audit firm, platform, competition program) to attest
+    pub fn register_attester(
+        ctx: Context<RegisterAttester>,
+        authority: Pubkey,
+        name: String,
+    ) -> Result<()> {
+        require!(!name.is_empty() && name.len() <= 32, ErrorCode::InvalidAttesterName);
+
+        let attester = &mut ctx.accounts.attester;
+        attester.authority = authority;
+        attester.name = name;
+        attester.active = true;
+        attester.issued_count = 0;
+        attester.bump = ctx.bumps.attester;
+
+        msg!("Attester registered: {}", authority);
+        Ok(())
+    }
+
+    /// Stop an attester from issuing; its existing attestations stand until
+    /// they expire or are revoked, by the attester or `admin_revoke_attestation`
+    pub fn deactivate_attester(ctx: Context<DeactivateAttester>) -> Result<()> {
+        ctx.accounts.attester.active = false;
+
+        msg!("Attester deactivated: {}", ctx.accounts.attester.authority);
+        Ok(())
+    }
+
+    /// Attest to something about an agent. `reference` ties the attestation to
+    /// an account it is about, such as a competition, or is default.
+    pub fn issue_attestation(
+        ctx: Context<IssueAttestation>,
+        kind: AttestationKind,
+        reference: Pubkey,
+        uri: String,
+        expires_at: i64,
+    ) -> Result<()> {
+        require!(ctx.accounts.attester.active, ErrorCode::AttesterInactive);
+        require!(uri.len() <= MAX_URI_LEN, ErrorCode::UriTooLong);
+
+        let now = Clock::get()?.unix_timestamp;
+        require!(expires_at == 0 || expires_at > now, ErrorCode::InvalidExpiry);
+
+        let attestation = &mut ctx.accounts.attestation;
+        attestation.registry = ctx.accounts.registry.key();
+        attestation.attester = ctx.accounts.attester.key();
+        attestation.kind = kind;
+        attestation.reference = reference;
+        attestation.uri = uri;
+        attestation.issued_at = now;
+        attestation.expires_at = expires_at;
+        attestation.active = true;
+        attestation.revoked = false;
+        attestation.bump = ctx.bumps.attestation;
+
+        ctx.accounts.attester.issued_count += 1;
+        let registry = &mut ctx.accounts.registry;
+        registry.adjust_attestations(kind, true);
+        registry.attestation_accounts = registry
+            .attestation_accounts
+            .checked_add(1)
+            .ok_or(ErrorCode::Overflow)?;
+
+        emit!(AttestationIssuedEvent {
+            registry: attestation.registry,
+            attester: attestation.attester,
+            attestation: attestation.key(),
+            kind,
+            reference,
+            expires_at,
+            reputation: ctx.accounts.registry.reputation(),
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Withdraw an attestation. The account stays so the revocation can be seen.
+    pub fn revoke_attestation(ctx: Context<RevokeAttestation>) -> Result<()> {
+        revoke(&mut ctx.accounts.attestation, &mut ctx.accounts.registry)
+    }
+
+    /// Revoke an attestation on the admin's authority, for attesters that
+    /// were compromised or can no longer act
+    pub fn admin_revoke_attestation(ctx: Context<AdminRevokeAttestation>) -> Result<()> {
+        revoke(&mut ctx.accounts.attestation, &mut ctx.accounts.registry)
+    }
+
+    /// The agent's reputation score, for clients to read by simulation
+    pub fn get_reputation(ctx: Context<GetReputation>) -> Result<u32> {
+        Ok(ctx.accounts.registry.reputation())
+    }
+
+    /// Drop an expired attestation from the agent's counts. Permissionless
+    /// so reputation doesn't wait on the attester.
+    pub fn expire_attestation(ctx: Context<ExpireAttestation>) -> Result<()> {
+        let attestation = &mut ctx.accounts.attestation;
+        require!(attestation.active, ErrorCode::AttestationInactive);
+        require!(
+            attestation.is_expired(Clock::get()?.unix_timestamp),
+            ErrorCode::AttestationNotExpired
+        );
+
+        attestation.active = false;
+        ctx.accounts.registry.adjust_attestations(attestation.kind, false);
+
+        Ok(())
+    }
+
+    /// Close an attestation that no longer counts, returning its rent
+    pub fn close_attestation(ctx: Context<CloseAttestation>) -> Result<()> {
+        require!(!ctx.accounts.attestation.active, ErrorCode::AttestationActive);
+
+        let registry = &mut ctx.accounts.registry;
+        registry.attestation_accounts = registry.attestation_accounts.saturating_sub(1);
+
+        Ok(())
+    }
+
+    /// Let the owner drop an attestation about their agent, e.g. before
+    /// closing it. The rent goes back to the attester.
+    pub fn discard_attestation(ctx: Context<DiscardAttestation>) -> Result<()> {
+        let attestation = &ctx.accounts.attestation;
+        let registry = &mut ctx.accounts.registry;
+        if attestation.active {
+            registry.adjust_attestations(attestation.kind, false);
+        }
+        registry.attestation_accounts = registry.attestation_accounts.saturating_sub(1);
+
+        Ok(())
+    }
+
+    /// Revoke a signer's revenue deposit authorization
+    pub fn remove_revenue_depositor(
+        ctx: Context<UpdateRegistryConfig>,
+        depositor: Pubkey,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let position = config
+            .revenue_depositors
+            .iter()
+            .position(|authorized| *authorized == depositor)
+            .ok_or(ErrorCode::DepositorNotAuthorized)?;
+
+        config.revenue_depositors.swap_remove(position);
+
+        msg!("Revenue depositor removed: {}", depositor);
+        Ok(())
+    }
+
+    /// Realloc a pre-versioning registry entry to the current layout, with the
+    /// payer covering the extra rent
+    pub fn migrate_agent_registry(
+        ctx: Context<MigrateAgentRegistry>,
+        canonical_id: String,
+    ) -> Result<()> {
+        let info = ctx.accounts.registry.to_account_info();
+        require!(
+            info.data_len() == 8 + AgentRegistryV0::INIT_SPACE,
+            ErrorCode::AlreadyMigrated
+        );
+
+        let legacy = {
+            let data = info.try_borrow_data()?;
+            require!(
+                data[..8] == AgentRegistry::DISCRIMINATOR,
+                ErrorCode::InvalidMigrationAccount
+            );
+            AgentRegistryV0::deserialize(&mut &data[8..])?
+        };
+
+        require_keys_eq!(
+            legacy.owner,
+            ctx.accounts.owner.key(),
+            ErrorCode::InvalidMigrationAccount
+        );
+        let expected = Pubkey::create_program_address(
+            &[b"agent", legacy.agent_id.as_bytes(), &[legacy.bump]],
+            ctx.program_id,
+        )
+        .map_err(|_| error!(ErrorCode::InvalidMigrationAccount))?;
+        require_keys_eq!(expected, info.key(), ErrorCode::InvalidMigrationAccount);
+
+        // Ids from before validation, like "MyAgent", keep their PDA but the
+        // lowercase form is reserved for their owner so it can't be squatted
+        match (
+            canonical_agent_id(&legacy.agent_id),
+            ctx.accounts.reserved_name.as_mut(),
+        ) {
+            (Some(canonical), Some(reserved)) => {
+                require!(canonical == canonical_id, ErrorCode::InvalidMigrationAccount);
+                reserved.name = canonical;
+                reserved.holder = legacy.owner;
+                reserved.bump = ctx.bumps.reserved_name;
+            }
+            (None, None) => {}
+            _ => return err!(ErrorCode::InvalidMigrationAccount),
+        }
+
+        let new_space = 8 + AgentRegistry::INIT_SPACE;
+        let rent_due = Rent::get()?
+            .minimum_balance(new_space)
+            .saturating_sub(info.lamports());
+        if rent_due > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.payer.to_account_info(),
+                        to: info.clone(),
+                    },
+                ),
+                rent_due,
+            )?;
+        }
+
+        info.realloc(new_space, true)?;
+        let migrated = legacy.migrate();
+        migrated.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+
+        msg!("Agent registry migrated to version {}", AGENT_REGISTRY_VERSION);
+        Ok(())
+    }
+}
+
+/// Agent ids are 1-32 lowercase ASCII letters, digits and single dashes, not
+/// starting or ending with a dash. Rejecting anything else, rather than
+/// normalizing it, keeps "MyAgent" and "myagent" from being separate PDAs.
+fn validate_agent_id(agent_id: &str) -> Result<()> {
+    require!(agent_id.len() <= MAX_AGENT_ID_LEN, ErrorCode::AgentIdTooLong);
+    require!(
+        !agent_id.is_empty()
+            && agent_id
+                .bytes()
+                .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+            && !agent_id.starts_with('-')
+            && !agent_id.ends_with('-')
+            && !agent_id.contains("--"),
+        ErrorCode::InvalidAgentId
+    );
+    Ok(())
+}
+
+/// Lowercase form of a legacy id registered before ids were validated, if it
+/// differs from the id and is itself a valid id
+fn canonical_agent_id(agent_id: &str) -> Option<String> {
+    let canonical = agent_id.to_ascii_lowercase();
+    (canonical != agent_id && validate_agent_id(&canonical).is_ok()).then_some(canonical)
+}
+
+/// Pay a split beneficiary its accrued revenue. Removed beneficiaries drop
+/// out once paid; `removed_only` restricts the payment to them.
+fn pay_split_share(
+    registry: &mut Account<AgentRegistry>,
+    split: &mut Account<RevenueSplit>,
+    beneficiary_info: &AccountInfo,
+    removed_only: bool,
+) -> Result<()> {
+    let wallet = beneficiary_info.key();
+    let position = split
+        .beneficiaries
+        .iter()
+        .position(|beneficiary| beneficiary.wallet == wallet)
+        .ok_or(ErrorCode::NotABeneficiary)?;
+
+    let beneficiary = &mut split.beneficiaries[position];
+    require!(!removed_only || beneficiary.bps == 0, ErrorCode::NotABeneficiary);
+    beneficiary.settle(registry.total_revenue)?;
+    let claimable = beneficiary.accrued;
+    require!(claimable > 0, ErrorCode::NoRevenueToClaim);
+    beneficiary.accrued = 0;
+
+    if beneficiary.bps == 0 {
+        split.beneficiaries.swap_remove(position);
+    }
+
+    let registry_info = registry.to_account_info();
+    require!(registry_info.lamports() >= claimable, ErrorCode::InsufficientBalance);
+
+    **registry_info.try_borrow_mut_lamports()? -= claimable;
+    **beneficiary_info.try_borrow_mut_lamports()? += claimable;
+
+    registry.claimed_revenue = registry
+        .claimed_revenue
+        .checked_add(claimable)
+        .ok_or(ErrorCode::Overflow)?;
+
+    emit!(SplitRevenueClaimedEvent {
+        registry: registry.key(),
+        beneficiary: wallet,
+        amount: claimable,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Pay a split beneficiary its accrued share of one mint's revenue, as
+/// `pay_split_share` does for SOL
+#[allow(clippy::too_many_arguments)]
+fn pay_token_split_share<'info>(
+    registry: &Account<'info, AgentRegistry>,
+    split: &Account<'info, RevenueSplit>,
+    ledger: &mut Account<'info, TokenRevenueLedger>,
+    vault: &Account<'info, TokenAccount>,
+    destination: &Account<'info, TokenAccount>,
+    token_program: &Program<'info, Token>,
+    wallet: Pubkey,
+    removed_only: bool,
+) -> Result<()> {
+    let bps = split
+        .beneficiaries
+        .iter()
+        .find(|beneficiary| beneficiary.wallet == wallet)
+        .map_or(0, |beneficiary| beneficiary.bps);
+    require!(!removed_only || bps == 0, ErrorCode::NotABeneficiary);
+    require!(
+        bps > 0 || ledger.split_accruals.iter().any(|accrual| accrual.wallet == wallet),
+        ErrorCode::NotABeneficiary
+    );
+
+    let position = ledger.settle_beneficiary(wallet, bps)?;
+    let claimable = ledger.split_accruals[position].accrued;
+    require!(claimable > 0, ErrorCode::NoRevenueToClaim);
+    require!(vault.amount >= claimable, ErrorCode::InsufficientBalance);
+
+    if bps == 0 {
+        ledger.split_accruals.swap_remove(position);
+    } else {
+        ledger.split_accruals[position].accrued = 0;
+    }
+    ledger.claimed_revenue = ledger
+        .claimed_revenue
+        .checked_add(claimable)
+        .ok_or(ErrorCode::Overflow)?;
+
+    let seeds = &[b"agent", registry.agent_id.as_bytes(), &[registry.bump]];
+    let signer = &[&seeds[..]];
+
+    token::transfer(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            Transfer {
+                from: vault.to_account_info(),
+                to: destination.to_account_info(),
+                authority: registry.to_account_info(),
+            },
+            signer,
+        ),
+        claimable,
+    )?;
+
+    emit!(TokenSplitRevenueClaimedEvent {
+        registry: registry.key(),
+        mint: ledger.mint,
+        beneficiary: wallet,
+        amount: claimable,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Mark an attestation revoked and drop it from the agent's counts
+fn revoke(attestation: &mut Account<Attestation>, registry: &mut Account<AgentRegistry>) -> Result<()> {
+    require!(!attestation.revoked, ErrorCode::AttestationRevoked);
+
+    if attestation.active {
+        registry.adjust_attestations(attestation.kind, false);
+    }
+    attestation.active = false;
+    attestation.revoked = true;
+
+    emit!(AttestationRevokedEvent {
+        registry: attestation.registry,
+        attester: attestation.attester,
+        attestation: attestation.key(),
+        kind: attestation.kind,
+        reputation: registry.reputation(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Only the holder of a reserved id can register it
+fn check_reserved_name(reserved_name: &AccountInfo, owner: &Pubkey) -> Result<()> {
+    if reserved_name.data_is_empty() {
+        return Ok(());
+    }
+
+    require_keys_eq!(*reserved_name.owner, crate::ID, ErrorCode::AgentIdReserved);
+    let reserved = ReservedName::try_deserialize(&mut &reserved_name.try_borrow_data()?[..])?;
+    require!(
+        reserved.holder != Pubkey::default() && reserved.holder == *owner,
+        ErrorCode::AgentIdReserved
+    );
+    Ok(())
+}
+
+fn validate_metadata(uri: &str, tags: &[String]) -> Result<()> {
+    require!(uri.len() <= MAX_URI_LEN, ErrorCode::UriTooLong);
+    require!(
+        tags.len() <= MAX_TAGS && tags.iter().all(|tag| !tag.is_empty() && tag.len() <= MAX_TAG_LEN),
+        ErrorCode::InvalidTags
+    );
+    Ok(())
+}
+
+/// Pay SOL from a signer, skipping zero amounts
+fn pay_sol<'info>(
+    from: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+
+    system_program::transfer(
+        CpiContext::new(
+            system_program.clone(),
+            system_program::Transfer {
+                from: from.clone(),
+                to: to.clone(),
+            },
+        ),
+        amount,
+    )
+}
+
+#[derive(Accounts)]
+#[instruction(agent_id: String)]
+pub struct RegisterAgent<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + AgentRegistry::INIT_SPACE,
+        seeds = [b"agent", agent_id.as_bytes()],
+        bump
+    )]
+    pub registry: Account<'info, AgentRegistry>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// CHECK: Tombstone of a closed entry with this id, which must not exist
+    #[account(seeds = [b"tombstone", agent_id.as_bytes()], bump)]
+    pub tombstone: UncheckedAccount<'info>,
+
+    /// CHECK: Reservation for this id, if any, checked in the handler
+    #[account(seeds = [b"reserved_name", agent_id.as_bytes()], bump)]
+    pub reserved_name: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(agent_id: String)]
+pub struct RegisterLinkedAgent<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AgentRegistry::INIT_SPACE,
+        seeds = [b"agent", agent_id.as_bytes()],
+        bump
+    )]
+    pub registry: Account<'info, AgentRegistry>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AgentLink::INIT_SPACE,
+        seeds = [b"link", agent_state.key().as_ref()],
+        bump
+    )]
+    pub link: Account<'info, AgentLink>,
+
+    /// Owned by agent-manager, at its canonical PDA and controlled by the owner
+    #[account(
+        seeds = [b"agent", agent_state.authority.as_ref()],
+        bump = agent_state.bump,
+        seeds::program = agent_manager::ID,
+        constraint = agent_state.authority == owner.key() @ ErrorCode::AgentStateOwnerMismatch
+    )]
+    pub agent_state: Account<'info, AgentState>,
+
+    pub owner: Signer<'info>,
+
+    /// Separate from the owner so PDA owners, which can't pay rent, can call this
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: Tombstone of a closed entry with this id, which must not exist
+    #[account(seeds = [b"tombstone", agent_id.as_bytes()], bump)]
+    pub tombstone: UncheckedAccount<'info>,
+
+    /// CHECK: Reservation for this id, if any, checked in the handler
+    #[account(seeds = [b"reserved_name", agent_id.as_bytes()], bump)]
+    pub reserved_name: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(agent_id: String)]
+pub struct RegisterFactoryAgent<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AgentRegistry::INIT_SPACE,
+        seeds = [b"agent", agent_id.as_bytes()],
+        bump
+    )]
+    pub registry: Account<'info, AgentRegistry>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AgentLink::INIT_SPACE,
+        seeds = [b"link", agent_state.key().as_ref()],
+        bump
+    )]
+    pub link: Account<'info, AgentLink>,
+
+    /// Owned by agent-manager, at its canonical PDA and controlled by the owner
+    #[account(
+        seeds = [b"agent", agent_state.authority.as_ref()],
+        bump = agent_state.bump,
+        seeds::program = agent_manager::ID,
+        constraint = agent_state.authority == owner.key() @ ErrorCode::AgentStateOwnerMismatch
+    )]
+    pub agent_state: Account<'info, AgentState>,
+
+    #[account(
+        seeds = [b"registry_config"],
+        bump = config.bump,
+        constraint = config.factory == factory.key() @ ErrorCode::UnauthorizedFactory
+    )]
+    pub config: Account<'info, RegistryConfig>,
+
+    /// Factory state PDA, signing through CPI
+    pub factory: Signer<'info>,
+
+    /// CHECK: Creator of the agent, becomes the registry owner
+    pub owner: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: Tombstone of a closed entry with this id, which must not exist
+    #[account(seeds = [b"tombstone", agent_id.as_bytes()], bump)]
+    pub tombstone: UncheckedAccount<'info>,
+
+    /// CHECK: Reservation for this id, if any, checked in the handler
+    #[account(seeds = [b"reserved_name", agent_id.as_bytes()], bump)]
+    pub reserved_name: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct LinkAgentState<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", registry.agent_id.as_bytes()],
+        bump = registry.bump,
+        has_one = owner
+    )]
+    pub registry: Account<'info, AgentRegistry>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AgentLink::INIT_SPACE,
+        seeds = [b"link", agent_state.key().as_ref()],
+        bump
+    )]
+    pub link: Account<'info, AgentLink>,
+
+    /// Owned by agent-manager, at its canonical PDA and controlled by the owner
+    #[account(
+        seeds = [b"agent", agent_state.authority.as_ref()],
+        bump = agent_state.bump,
+        seeds::program = agent_manager::ID,
+        constraint = agent_state.authority == owner.key() @ ErrorCode::AgentStateOwnerMismatch
+    )]
+    pub agent_state: Account<'info, AgentState>,
+
+    #[account(
+        mut,
+        seeds = [b"metadata", registry.key().as_ref()],
+        bump = metadata.bump,
+        has_one = registry
+    )]
+    pub metadata: Option<Account<'info, AgentMetadata>>,
+
+    pub owner: Signer<'info>,
+
+    /// Separate from the owner so PDA owners, which can't pay rent, can call this
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRevenue<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", registry.agent_id.as_bytes()],
+        bump = registry.bump,
+        has_one = owner
+    )]
+    pub registry: Account<'info, AgentRegistry>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct TransferOwnership<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", registry.agent_id.as_bytes()],
+        bump = registry.bump,
+        has_one = owner
+    )]
+    pub registry: Account<'info, AgentRegistry>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptOwnership<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", registry.agent_id.as_bytes()],
+        bump = registry.bump,
+        constraint = registry.pending_owner == pending_owner.key() @ ErrorCode::NotPendingOwner
+    )]
+    pub registry: Account<'info, AgentRegistry>,
+
+    pub pending_owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DepositRevenue<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", registry.agent_id.as_bytes()],
+        bump = registry.bump
+    )]
+    pub registry: Account<'info, AgentRegistry>,
+
+    #[account(
+        seeds = [b"registry_config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, RegistryConfig>,
+
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimTips<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", registry.agent_id.as_bytes()],
+        bump = registry.bump,
+        has_one = owner
+    )]
+    pub registry: Account<'info, AgentRegistry>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CreateRevenueSplit<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", registry.agent_id.as_bytes()],
+        bump = registry.bump,
+        has_one = owner
+    )]
+    pub registry: Account<'info, AgentRegistry>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + RevenueSplit::INIT_SPACE,
+        seeds = [b"revenue_split", registry.key().as_ref()],
+        bump
+    )]
+    pub split: Account<'info, RevenueSplit>,
+
+    pub owner: Signer<'info>,
+
+    /// Separate from the owner so PDA owners, which can't pay rent, can call this
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateRevenueSplit<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", registry.agent_id.as_bytes()],
+        bump = registry.bump,
+        has_one = owner
+    )]
+    pub registry: Account<'info, AgentRegistry>,
+
+    #[account(
+        mut,
+        seeds = [b"revenue_split", registry.key().as_ref()],
+        bump = split.bump,
+        has_one = registry
+    )]
+    pub split: Account<'info, RevenueSplit>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimSplitRevenue<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", registry.agent_id.as_bytes()],
+        bump = registry.bump
+    )]
+    pub registry: Account<'info, AgentRegistry>,
+
+    #[account(
+        mut,
+        seeds = [b"revenue_split", registry.key().as_ref()],
+        bump = split.bump,
+        has_one = registry
+    )]
+    pub split: Account<'info, RevenueSplit>,
+
+    #[account(mut)]
+    pub beneficiary: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SettleRemovedBeneficiary<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", registry.agent_id.as_bytes()],
+        bump = registry.bump,
+        has_one = owner
+    )]
+    pub registry: Account<'info, AgentRegistry>,
+
+    #[account(
+        mut,
+        seeds = [b"revenue_split", registry.key().as_ref()],
+        bump = split.bump,
+        has_one = registry
+    )]
+    pub split: Account<'info, RevenueSplit>,
+
+    /// CHECK: Removed beneficiary's wallet, matched against the split
+    #[account(mut)]
+    pub beneficiary: UncheckedAccount<'info>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct OpenTokenLedger<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", registry.agent_id.as_bytes()],
+        bump = registry.bump
+    )]
+    pub registry: Account<'info, AgentRegistry>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + TokenRevenueLedger::INIT_SPACE,
+        seeds = [b"token_revenue", registry.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub ledger: Account<'info, TokenRevenueLedger>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"token_vault", ledger.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = registry
+    )]
+    pub vault: Account<'info, TokenAccount>,
+
+    pub mint: Account<'info, Mint>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct DepositRevenueToken<'info> {
+    #[account(
+        seeds = [b"agent", registry.agent_id.as_bytes()],
+        bump = registry.bump
+    )]
+    pub registry: Account<'info, AgentRegistry>,
+
+    #[account(
+        mut,
+        seeds = [b"token_revenue", registry.key().as_ref(), ledger.mint.as_ref()],
+        bump = ledger.bump,
+        has_one = registry,
+        has_one = vault
+    )]
+    pub ledger: Account<'info, TokenRevenueLedger>,
+
+    #[account(mut)]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"registry_config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, RegistryConfig>,
+
+    #[account(mut, token::mint = ledger.mint, token::authority = depositor)]
+    pub depositor_token_account: Account<'info, TokenAccount>,
+
+    pub depositor: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRevenueToken<'info> {
+    #[account(
+        seeds = [b"agent", registry.agent_id.as_bytes()],
+        bump = registry.bump,
+        has_one = owner
+    )]
+    pub registry: Account<'info, AgentRegistry>,
+
+    #[account(
+        mut,
+        seeds = [b"token_revenue", registry.key().as_ref(), ledger.mint.as_ref()],
+        bump = ledger.bump,
+        has_one = registry,
+        has_one = vault
+    )]
+    pub ledger: Account<'info, TokenRevenueLedger>,
+
+    #[account(mut)]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(mut, token::mint = ledger.mint)]
+    pub owner_token_account: Account<'info, TokenAccount>,
+
+    /// Required while a revenue split is active
+    #[account(
+        seeds = [b"revenue_split", registry.key().as_ref()],
+        bump = split.bump,
+        has_one = registry
+    )]
+    pub split: Option<Account<'info, RevenueSplit>>,
+
+    pub owner: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimSplitRevenueToken<'info> {
+    #[account(
+        seeds = [b"agent", registry.agent_id.as_bytes()],
+        bump = registry.bump
+    )]
+    pub registry: Account<'info, AgentRegistry>,
+
+    #[account(
+        seeds = [b"revenue_split", registry.key().as_ref()],
+        bump = split.bump,
+        has_one = registry
+    )]
+    pub split: Account<'info, RevenueSplit>,
+
+    #[account(
+        mut,
+        seeds = [b"token_revenue", registry.key().as_ref(), ledger.mint.as_ref()],
+        bump = ledger.bump,
+        has_one = registry,
+        has_one = vault
+    )]
+    pub ledger: Account<'info, TokenRevenueLedger>,
+
+    #[account(mut)]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(mut, token::mint = ledger.mint, token::authority = beneficiary)]
+    pub beneficiary_token_account: Account<'info, TokenAccount>,
+
+    pub beneficiary: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SettleRemovedBeneficiaryToken<'info> {
+    #[account(
+        seeds = [b"agent", registry.agent_id.as_bytes()],
+        bump = registry.bump,
+        has_one = owner
+    )]
+    pub registry: Account<'info, AgentRegistry>,
+
+    #[account(
+        seeds = [b"revenue_split", registry.key().as_ref()],
+        bump = split.bump,
+        has_one = registry
+    )]
+    pub split: Account<'info, RevenueSplit>,
+
+    #[account(
+        mut,
+        seeds = [b"token_revenue", registry.key().as_ref(), ledger.mint.as_ref()],
+        bump = ledger.bump,
+        has_one = registry,
+        has_one = vault
+    )]
+    pub ledger: Account<'info, TokenRevenueLedger>,
+
+    #[account(mut)]
+    pub vault: Account<'info, TokenAccount>,
+
+    /// CHECK: Removed beneficiary's wallet, matched against the ledger
+    pub beneficiary: UncheckedAccount<'info>,
+
+    #[account(mut, token::mint = ledger.mint, token::authority = beneficiary)]
+    pub beneficiary_token_account: Account<'info, TokenAccount>,
+
+    pub owner: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ListAgent<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", registry.agent_id.as_bytes()],
+        bump = registry.bump,
+        has_one = owner
+    )]
+    pub registry: Account<'info, AgentRegistry>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Listing::INIT_SPACE,
+        seeds = [b"listing", registry.key().as_ref()],
+        bump
+    )]
+    pub listing: Account<'info, Listing>,
+
+    pub owner: Signer<'info>,
+
+    /// Separate from the owner so PDA owners, which can't pay rent, can call this
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DelistAgent<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", registry.agent_id.as_bytes()],
+        bump = registry.bump,
+        has_one = owner
+    )]
+    pub registry: Account<'info, AgentRegistry>,
+
+    #[account(
+        mut,
+        seeds = [b"listing", registry.key().as_ref()],
+        bump = listing.bump,
+        has_one = registry,
+        has_one = rent_payer,
+        close = rent_payer
+    )]
+    pub listing: Account<'info, Listing>,
+
+    pub owner: Signer<'info>,
+
+    /// CHECK: Receives the listing rent, checked against the listing
+    #[account(mut)]
+    pub rent_payer: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct BuyAgent<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", registry.agent_id.as_bytes()],
+        bump = registry.bump
+    )]
+    pub registry: Account<'info, AgentRegistry>,
+
+    #[account(
+        mut,
+        seeds = [b"listing", registry.key().as_ref()],
+        bump = listing.bump,
+        has_one = registry,
+        has_one = seller,
+        has_one = rent_payer,
+        close = rent_payer
+    )]
+    pub listing: Account<'info, Listing>,
+
+    #[account(
+        seeds = [b"registry_config"],
+        bump = config.bump,
+        has_one = treasury
+    )]
+    pub config: Account<'info, RegistryConfig>,
+
+    /// CHECK: Receives the sale proceeds, checked against the listing
+    #[account(mut)]
+    pub seller: UncheckedAccount<'info>,
+
+    /// CHECK: Receives the marketplace fee, checked against the config
+    #[account(mut)]
+    pub treasury: UncheckedAccount<'info>,
+
+    /// CHECK: Receives the listing rent, checked against the listing
+    #[account(mut)]
+    pub rent_payer: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    /// Token accounts for listings priced in an SPL mint
+    #[account(mut)]
+    pub buyer_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub seller_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub treasury_token_account: Option<Account<'info, TokenAccount>>,
+    pub token_program: Option<Program<'info, Token>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(category: AgentCategory)]
+pub struct CreateMetadata<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", registry.agent_id.as_bytes()],
+        bump = registry.bump,
+        has_one = owner
+    )]
+    pub registry: Account<'info, AgentRegistry>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AgentMetadata::INIT_SPACE,
+        seeds = [b"metadata", registry.key().as_ref()],
+        bump
+    )]
+    pub metadata: Account<'info, AgentMetadata>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + CategoryIndexEntry::INIT_SPACE,
+        seeds = [b"category_index".as_ref(), &[category as u8], registry.key().as_ref()],
+        bump
+    )]
+    pub category_index: Account<'info, CategoryIndexEntry>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + OwnerIndexEntry::INIT_SPACE,
+        seeds = [b"owner_index", owner.key().as_ref(), registry.key().as_ref()],
+        bump
+    )]
+    pub owner_index: Account<'info, OwnerIndexEntry>,
+
+    #[account(
+        seeds = [b"link", link.agent_state.as_ref()],
+        bump = link.bump,
+        has_one = registry
+    )]
+    pub link: Option<Account<'info, AgentLink>>,
+
+    pub owner: Signer<'info>,
+
+    /// Separate from the owner so PDA owners, which can't pay rent, can call this
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateMetadata<'info> {
+    #[account(
+        seeds = [b"agent", registry.agent_id.as_bytes()],
+        bump = registry.bump,
+        has_one = owner
+    )]
+    pub registry: Account<'info, AgentRegistry>,
+
+    #[account(
+        mut,
+        seeds = [b"metadata", registry.key().as_ref()],
+        bump = metadata.bump,
+        has_one = registry
+    )]
+    pub metadata: Account<'info, AgentMetadata>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(category: AgentCategory)]
+pub struct SetCategory<'info> {
+    #[account(
+        seeds = [b"agent", registry.agent_id.as_bytes()],
+        bump = registry.bump,
+        has_one = owner
+    )]
+    pub registry: Account<'info, AgentRegistry>,
+
+    #[account(
+        mut,
+        seeds = [b"metadata", registry.key().as_ref()],
+        bump = metadata.bump,
+        has_one = registry
+    )]
+    pub metadata: Account<'info, AgentMetadata>,
+
+    #[account(
+        mut,
+        seeds = [b"category_index".as_ref(), &[metadata.category as u8], registry.key().as_ref()],
+        bump = old_category_index.bump,
+        has_one = rent_payer,
+        close = rent_payer
+    )]
+    pub old_category_index: Account<'info, CategoryIndexEntry>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + CategoryIndexEntry::INIT_SPACE,
+        seeds = [b"category_index".as_ref(), &[category as u8], registry.key().as_ref()],
+        bump
+    )]
+    pub new_category_index: Account<'info, CategoryIndexEntry>,
+
+    pub owner: Signer<'info>,
+
+    /// CHECK: Receives the old entry's rent, checked against the entry
+    #[account(mut)]
+    pub rent_payer: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReindexOwner<'info> {
+    #[account(
+        seeds = [b"agent", registry.agent_id.as_bytes()],
+        bump = registry.bump
+    )]
+    pub registry: Account<'info, AgentRegistry>,
+
+    #[account(
+        mut,
+        seeds = [b"owner_index", stale_index.owner.as_ref(), registry.key().as_ref()],
+        bump = stale_index.bump,
+        has_one = registry,
+        has_one = rent_payer,
+        close = rent_payer
+    )]
+    pub stale_index: Account<'info, OwnerIndexEntry>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + OwnerIndexEntry::INIT_SPACE,
+        seeds = [b"owner_index", registry.owner.as_ref(), registry.key().as_ref()],
+        bump
+    )]
+    pub owner_index: Account<'info, OwnerIndexEntry>,
+
+    /// CHECK: Receives the stale entry's rent, checked against the entry
+    #[account(mut)]
+    pub rent_payer: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct ReserveName<'info> {
+    #[account(
+        seeds = [b"registry_config"],
+        bump = config.bump,
+        has_one = admin
+    )]
+    pub config: Account<'info, RegistryConfig>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + ReservedName::INIT_SPACE,
+        seeds = [b"reserved_name", name.as_bytes()],
+        bump
+    )]
+    pub reserved_name: Account<'info, ReservedName>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseName<'info> {
+    #[account(
+        seeds = [b"registry_config"],
+        bump = config.bump,
+        has_one = admin
+    )]
+    pub config: Account<'info, RegistryConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"reserved_name", reserved_name.name.as_bytes()],
+        bump = reserved_name.bump,
+        close = admin
+    )]
+    pub reserved_name: Account<'info, ReservedName>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(authority: Pubkey)]
+pub struct RegisterAttester<'info> {
+    #[account(
+        seeds = [b"registry_config"],
+        bump = config.bump,
+        has_one = admin
+    )]
+    pub config: Account<'info, RegistryConfig>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Attester::INIT_SPACE,
+        seeds = [b"attester", authority.as_ref()],
+        bump
+    )]
+    pub attester: Account<'info, Attester>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DeactivateAttester<'info> {
+    #[account(
+        seeds = [b"registry_config"],
+        bump = config.bump,
+        has_one = admin
+    )]
+    pub config: Account<'info, RegistryConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"attester", attester.authority.as_ref()],
+        bump = attester.bump
+    )]
+    pub attester: Account<'info, Attester>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(kind: AttestationKind, reference: Pubkey)]
+pub struct IssueAttestation<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", registry.agent_id.as_bytes()],
+        bump = registry.bump
+    )]
+    pub registry: Account<'info, AgentRegistry>,
+
+    #[account(
+        mut,
+        seeds = [b"attester", authority.key().as_ref()],
+        bump = attester.bump,
+        has_one = authority
+    )]
+    pub attester: Account<'info, Attester>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Attestation::INIT_SPACE,
+        seeds = [
+            b"attestation".as_ref(),
+            registry.key().as_ref(),
+            attester.key().as_ref(),
+            &[kind as u8],
+            reference.as_ref()
+        ],
+        bump
+    )]
+    pub attestation: Account<'info, Attestation>,
+
+    pub authority: Signer<'info>,
+
+    /// Separate from the authority so PDA attesters can issue through CPI
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeAttestation<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", registry.agent_id.as_bytes()],
+        bump = registry.bump
+    )]
+    pub registry: Account<'info, AgentRegistry>,
+
+    #[account(
+        seeds = [b"attester", authority.key().as_ref()],
+        bump = attester.bump,
+        has_one = authority
+    )]
+    pub attester: Account<'info, Attester>,
+
+    #[account(
+        mut,
+        has_one = registry,
+        has_one = attester
+    )]
+    pub attestation: Account<'info, Attestation>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AdminRevokeAttestation<'info> {
+    #[account(
+        seeds = [b"registry_config"],
+        bump = config.bump,
+        has_one = admin
+    )]
+    pub config: Account<'info, RegistryConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"agent", registry.agent_id.as_bytes()],
+        bump = registry.bump
+    )]
+    pub registry: Account<'info, AgentRegistry>,
+
+    #[account(mut, has_one = registry)]
+    pub attestation: Account<'info, Attestation>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GetReputation<'info> {
+    #[account(
+        seeds = [b"agent", registry.agent_id.as_bytes()],
+        bump = registry.bump
+    )]
+    pub registry: Account<'info, AgentRegistry>,
+}
+
+#[derive(Accounts)]
+pub struct ExpireAttestation<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", registry.agent_id.as_bytes()],
+        bump = registry.bump
+    )]
+    pub registry: Account<'info, AgentRegistry>,
+
+    #[account(mut, has_one = registry)]
+    pub attestation: Account<'info, Attestation>,
+}
+
+#[derive(Accounts)]
+pub struct CloseAttestation<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", registry.agent_id.as_bytes()],
+        bump = registry.bump
+    )]
+    pub registry: Account<'info, AgentRegistry>,
+
+    #[account(
+        seeds = [b"attester", authority.key().as_ref()],
+        bump = attester.bump,
+        has_one = authority
+    )]
+    pub attester: Account<'info, Attester>,
+
+    #[account(
+        mut,
+        has_one = registry,
+        has_one = attester,
+        close = authority
+    )]
+    pub attestation: Account<'info, Attestation>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DiscardAttestation<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", registry.agent_id.as_bytes()],
+        bump = registry.bump,
+        has_one = owner
+    )]
+    pub registry: Account<'info, AgentRegistry>,
+
+    #[account(
+        seeds = [b"attester", attester_authority.key().as_ref()],
+        bump = attester.bump
+    )]
+    pub attester: Account<'info, Attester>,
+
+    #[account(
+        mut,
+        has_one = registry,
+        has_one = attester,
+        close = attester_authority
+    )]
+    pub attestation: Account<'info, Attestation>,
+
+    /// CHECK: Receives the attestation's rent, checked against the attester
+    #[account(mut)]
+    pub attester_authority: UncheckedAccount<'info>,
+
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseTokenLedger<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", registry.agent_id.as_bytes()],
+        bump = registry.bump,
+        has_one = owner
+    )]
+    pub registry: Account<'info, AgentRegistry>,
+
+    #[account(
+        mut,
+        seeds = [b"token_revenue", registry.key().as_ref(), ledger.mint.as_ref()],
+        bump = ledger.bump,
+        has_one = registry,
+        has_one = vault,
+        close = owner
+    )]
+    pub ledger: Account<'info, TokenRevenueLedger>,
+
+    #[account(mut)]
+    pub vault: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CloseAgent<'info> {
+    #[account(
+        mut,
+        seeds = [b"agent", registry.agent_id.as_bytes()],
+        bump = registry.bump,
+        has_one = owner,
+        close = owner
+    )]
+    pub registry: Account<'info, AgentRegistry>,
+
+    #[account(mut, has_one = registry, close = owner)]
+    pub link: Option<Account<'info, AgentLink>>,
+
+    #[account(
+        mut,
+        seeds = [b"metadata", registry.key().as_ref()],
+        bump = metadata.bump,
+        has_one = registry,
+        close = owner
+    )]
+    pub metadata: Option<Account<'info, AgentMetadata>>,
+
+    #[account(mut, has_one = registry, close = owner)]
+    pub category_index: Option<Account<'info, CategoryIndexEntry>>,
+
+    #[account(mut, has_one = registry, close = owner)]
+    pub owner_index: Option<Account<'info, OwnerIndexEntry>>,
+
+    #[account(
+        mut,
+        seeds = [b"revenue_split", registry.key().as_ref()],
+        bump = split.bump,
+        has_one = registry,
+        close = owner
+    )]
+    pub split: Option<Account<'info, RevenueSplit>>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Tombstone::INIT_SPACE,
+        seeds = [b"tombstone", registry.agent_id.as_bytes()],
+        bump
+    )]
+    pub tombstone: Option<Account<'info, Tombstone>>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// Pays the tombstone's rent
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClearTombstone<'info> {
+    #[account(
+        mut,
+        seeds = [b"tombstone", tombstone.agent_id.as_bytes()],
+        bump = tombstone.bump,
+        has_one = rent_payer,
+        close = rent_payer
+    )]
+    pub tombstone: Account<'info, Tombstone>,
+
+    /// CHECK: Receives the tombstone's rent, checked against the tombstone
+    #[account(mut)]
+    pub rent_payer: UncheckedAccount<'info>,
+
+    pub caller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeRegistryConfig<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + RegistryConfig::INIT_SPACE,
+        seeds = [b"registry_config"],
+        bump
+    )]
+    pub config: Account<'info, RegistryConfig>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::AgentRegistry>,
+
+    /// Only the upgrade authority can create the config and become its admin
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(admin.key())
+            @ ErrorCode::UnauthorizedAdmin
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateRegistryConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"registry_config"],
+        bump = config.bump,
+        has_one = admin
+    )]
+    pub config: Account<'info, RegistryConfig>,
+
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(canonical_id: String)]
+pub struct MigrateAgentRegistry<'info> {
+    #[account(mut, owner = crate::ID)]
+    /// CHECK: Legacy registry entry, decoded and checked in the handler
+    pub registry: UncheckedAccount<'info>,
+
+    /// Reservation of the lowercase form of a non-canonical legacy id
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + ReservedName::INIT_SPACE,
+        seeds = [b"reserved_name", canonical_id.as_bytes()],
+        bump
+    )]
+    pub reserved_name: Option<Account<'info, ReservedName>>,
+
+    pub owner: Signer<'info>,
+
+    /// Separate from the owner so PDA owners, which can't pay rent, can call this
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct AgentRegistry {
+    pub version: u8,
+    #[max_len(32)]
+    pub agent_id: String,
+    #[max_len(64)]
+    pub name: String,
+    pub owner: Pubkey,
+    pub created_at: i64,
+    pub total_revenue: u64,
+    pub claimed_revenue: u64,
+    pub bump: u8,
+    /// Deposits from signers outside the config's depositor list
+    pub total_tips: u64,
+    pub claimed_tips: u64,
+    /// Revenue is claimed per beneficiary instead of by the owner
+    pub has_revenue_split: bool,
+    /// Owner-elect that must accept a transfer, default when none is pending
+    pub pending_owner: Pubkey,
+    /// Active attestations per `AttestationKind`
+    pub attestation_counts: [u16; 4],
+    /// `CHILD_*` flags for the entry's link, metadata, split and listing accounts
+    pub child_accounts: u8,
+    /// Open token revenue ledgers
+    pub token_ledger_count: u8,
+    /// Open `Attestation` accounts, active or not
+    pub attestation_accounts: u16,
+    /// Spare space for new fields without another migration
+    pub reserved: [u8; 3],
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct RevenueSplit {
+    pub registry: Pubkey,
+    /// Active beneficiaries, plus removed ones with revenue still to claim
+    #[max_len(MAX_SPLIT_ENTRIES)]
+    pub beneficiaries: Vec<Beneficiary>,
+    /// Proposed split waiting out the timelock, empty when none
+    #[max_len(MAX_BENEFICIARIES)]
+    pub pending: Vec<BeneficiaryShare>,
+    pub pending_eta: i64,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct Beneficiary {
+    pub wallet: Pubkey,
+    pub bps: u16,
+    /// `total_revenue` up to which this beneficiary's share is settled
+    pub checkpoint: u64,
+    /// Settled revenue not yet claimed
+    pub accrued: u64,
+}
+
+impl Beneficiary {
+    /// Credit the share of revenue deposited since the last checkpoint
+    pub fn settle(&mut self, total_revenue: u64) -> Result<()> {
+        let delta = total_revenue.saturating_sub(self.checkpoint);
+        let share = (delta as u128 * self.bps as u128 / MAX_BPS as u128) as u64;
+        self.accrued = self.accrued.checked_add(share).ok_or(ErrorCode::Overflow)?;
+        self.checkpoint = total_revenue;
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct BeneficiaryShare {
+    pub wallet: Pubkey,
+    pub bps: u16,
+}
+
+/// Discovery metadata for an agent. Fixed-size fields come first so clients
+/// can filter with memcmp: `registry` at offset 8, `category` at offset 40.
+#[account]
+#[derive(InitSpace)]
+pub struct AgentMetadata {
+    pub registry: Pubkey,
+    pub category: AgentCategory,
+    pub source: CreationSource,
+    /// Set when `agent_state` and `token_mint` come from a verified `AgentLink`
+    pub linked: bool,
+    /// Linked agent-manager `AgentState`, owner-declared unless `linked`
+    pub agent_state: Pubkey,
+    pub token_mint: Pubkey,
+    #[max_len(200)]
+    pub uri: String,
+    #[max_len(5, 32)]
+    pub tags: Vec<String>,
+    pub updated_at: i64,
+    pub bump: u8,
+}
+
+impl AgentMetadata {
+    pub fn apply_link(&mut self, link: &AgentLink) {
+        self.source = link.source;
+        self.linked = true;
+        self.agent_state = link.agent_state;
+        self.token_mint = link.token_mint;
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum AgentCategory {
+    Trading,
+    Research,
+    Social,
+    Gaming,
+    Defi,
+    Other,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum CreationSource {
+    Manual,
+    Factory,
+}
+
+/// One agent in a category, found by memcmp on `category` at offset 8
+#[account]
+#[derive(InitSpace)]
+pub struct CategoryIndexEntry {
+    pub category: AgentCategory,
+    pub registry: Pubkey,
+    pub rent_payer: Pubkey,
+    pub bump: u8,
+}
+
+/// One agent of an owner, found by memcmp on `owner` at offset 8. Entries
+/// go stale on ownership changes until `reindex_owner` replaces them.
+#[account]
+#[derive(InitSpace)]
+pub struct OwnerIndexEntry {
+    pub owner: Pubkey,
+    pub registry: Pubkey,
+    pub rent_payer: Pubkey,
+    pub bump: u8,
+}
+
+/// An authority allowed to attest to agents
+#[account]
+#[derive(InitSpace)]
+pub struct Attester {
+    pub authority: Pubkey,
+    #[max_len(32)]
+    pub name: String,
+    pub active: bool,
+    pub issued_count: u64,
+    pub bump: u8,
+}
+
+/// A claim an attester makes about an agent. Clients can list an agent's
+/// attestations by memcmp on `registry` at offset 8, or an attester's at 40.
+#[account]
+#[derive(InitSpace)]
+pub struct Attestation {
+    pub registry: Pubkey,
+    pub attester: Pubkey,
+    pub kind: AttestationKind,
+    /// Account the attestation is about, such as a competition, or default
+    pub reference: Pubkey,
+    /// Off-chain report or evidence
+    #[max_len(200)]
+    pub uri: String,
+    pub issued_at: i64,
+    /// 0 for attestations that never expire
+    pub expires_at: i64,
+    /// Counted towards the agent's reputation
+    pub active: bool,
+    pub revoked: bool,
+    pub bump: u8,
+}
+
+impl Attestation {
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at != 0 && now >= self.expires_at
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum AttestationKind {
+    StrategyAudit,
+    KycCreator,
+    CompetitionWin,
+    Other,
+}
+
+/// Agent id held back by the admin, e.g. a brand name
+#[account]
+#[derive(InitSpace)]
+pub struct ReservedName {
+    #[max_len(32)]
+    pub name: String,
+    /// The only owner allowed to register the id, or default for nobody
+    pub holder: Pubkey,
+    pub bump: u8,
+}
+
+/// Left behind by `close_agent` to hold a closed agent's id for a cooldown
+#[account]
+#[derive(InitSpace)]
+pub struct Tombstone {
+    #[max_len(32)]
+    pub agent_id: String,
+    pub previous_owner: Pubkey,
+    pub rent_payer: Pubkey,
+    pub closed_at: i64,
+    pub reusable_at: i64,
+    pub bump: u8,
+}
+
+/// Revenue paid to an agent in one SPL mint
+#[account]
+#[derive(InitSpace)]
+pub struct TokenRevenueLedger {
+    pub registry: Pubkey,
+    pub mint: Pubkey,
+    /// Registry-owned token account holding the deposits
+    pub vault: Pubkey,
+    pub total_revenue: u64,
+    pub claimed_revenue: u64,
+    pub total_tips: u64,
+    pub claimed_tips: u64,
+    pub bump: u8,
+    /// `total_revenue` when the current revenue split took effect
+    pub split_checkpoint: u64,
+    /// Beneficiaries' settlement of this mint's revenue under the split;
+    /// one without an entry is settled from `split_checkpoint`
+    #[max_len(MAX_SPLIT_ENTRIES)]
+    pub split_accruals: Vec<SplitAccrual>,
+}
+
+impl TokenRevenueLedger {
+    /// Credit every active beneficiary's share of revenue deposited since
+    /// its last settlement
+    pub fn settle_split(&mut self, split: &RevenueSplit) -> Result<()> {
+        for beneficiary in split.beneficiaries.iter().filter(|beneficiary| beneficiary.bps > 0) {
+            self.settle_beneficiary(beneficiary.wallet, beneficiary.bps)?;
+        }
+        Ok(())
+    }
+
+    /// Settle one beneficiary at `bps`, returning the index of its entry
+    pub fn settle_beneficiary(&mut self, wallet: Pubkey, bps: u16) -> Result<usize> {
+        let position = match self
+            .split_accruals
+            .iter()
+            .position(|accrual| accrual.wallet == wallet)
+        {
+            Some(position) => position,
+            None => {
+                require!(
+                    self.split_accruals.len() < MAX_SPLIT_ENTRIES,
+                    ErrorCode::TooManySplitEntries
+                );
+                self.split_accruals.push(SplitAccrual {
+                    wallet,
+                    checkpoint: self.split_checkpoint,
+                    accrued: 0,
+                });
+                self.split_accruals.len() - 1
+            }
+        };
+
+        let total_revenue = self.total_revenue;
+        let accrual = &mut self.split_accruals[position];
+        let delta = total_revenue.saturating_sub(accrual.checkpoint);
+        let share = (delta as u128 * bps as u128 / MAX_BPS as u128) as u64;
+        accrual.accrued = accrual.accrued.checked_add(share).ok_or(ErrorCode::Overflow)?;
+        accrual.checkpoint = total_revenue;
+        Ok(position)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct SplitAccrual {
+    pub wallet: Pubkey,
+    /// Ledger `total_revenue` up to which this share is settled
+    pub checkpoint: u64,
+    /// Settled revenue not yet claimed
+    pub accrued: u64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct RegistryConfig {
+    pub admin: Pubkey,
+    /// Signers whose deposits count towards `total_revenue`
+    #[max_len(16)]
+    pub revenue_depositors: Vec<Pubkey>,
+    /// Receives marketplace fees
+    pub treasury: Pubkey,
+    pub marketplace_fee_bps: u16,
+    /// Factory state PDA allowed to register factory-launched agents
+    pub factory: Pubkey,
+    pub bump: u8,
+}
+
+/// An agent offered for sale
+#[account]
+#[derive(InitSpace)]
+pub struct Listing {
+    pub registry: Pubkey,
+    pub seller: Pubkey,
+    pub price: u64,
+    /// SPL mint the price is in, default for SOL
+    pub currency: Pubkey,
+    pub revenue_policy: ListingRevenuePolicy,
+    pub rent_payer: Pubkey,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+/// What happens to unclaimed revenue when a listed agent is sold
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub enum ListingRevenuePolicy {
+    /// Pay unclaimed SOL and token revenue and tips to the seller at the sale
+    SettleToSeller,
+    /// Leave unclaimed revenue for the buyer
+    TransferWithAgent,
+}
+
+impl RegistryConfig {
+    pub fn is_depositor(&self, key: &Pubkey) -> bool {
+        self.revenue_depositors.contains(key)
+    }
+}
+
+impl AgentRegistry {
+    /// Basic reputation score: the number of active attestations
+    pub fn reputation(&self) -> u32 {
+        self.attestation_counts.iter().map(|count| *count as u32).sum()
+    }
+
+    fn adjust_attestations(&mut self, kind: AttestationKind, added: bool) {
+        let count = &mut self.attestation_counts[kind as usize];
+        *count = if added {
+            count.saturating_add(1)
+        } else {
+            count.saturating_sub(1)
+        };
+    }
+
+    /// A fresh entry on the current layout
+    pub fn new(agent_id: String, name: String, owner: Pubkey, created_at: i64, bump: u8) -> Self {
+        Self {
+            version: AGENT_REGISTRY_VERSION,
+            agent_id,
+            name,
+            owner,
+            created_at,
+            total_revenue: 0,
+            claimed_revenue: 0,
+            bump,
+            total_tips: 0,
+            claimed_tips: 0,
+            has_revenue_split: false,
+            pending_owner: Pubkey::default(),
+            attestation_counts: [0; 4],
+            child_accounts: 0,
+            token_ledger_count: 0,
+            attestation_accounts: 0,
+            reserved: [0; 3],
+        }
+    }
+}
+
+/// Canonical link between a registry entry and an agent-manager `AgentState`.
+/// Keyed by the agent state, so each agent has at most one registry identity.
+#[account]
+#[derive(InitSpace)]
+pub struct AgentLink {
+    pub registry: Pubkey,
+    pub agent_state: Pubkey,
+    pub token_mint: Pubkey,
+    pub source: CreationSource,
+    pub linked_at: i64,
+    pub bump: u8,
+}
+
+/// `AgentRegistry` layout before versioning, kept to migrate existing entries
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace)]
+pub struct AgentRegistryV0 {
+    #[max_len(32)]
+    pub agent_id: String,
+    #[max_len(64)]
+    pub name: String,
+    pub owner: Pubkey,
+    pub created_at: i64,
+    pub total_revenue: u64,
+    pub claimed_revenue: u64,
+    pub bump: u8,
+}
+
+impl AgentRegistryV0 {
+    pub fn migrate(self) -> AgentRegistry {
+        AgentRegistry {
+            version: AGENT_REGISTRY_VERSION,
+            agent_id: self.agent_id,
+            name: self.name,
+            owner: self.owner,
+            created_at: self.created_at,
+            total_revenue: self.total_revenue,
+            claimed_revenue: self.claimed_revenue,
+            bump: self.bump,
+            total_tips: 0,
+            claimed_tips: 0,
+            has_revenue_split: false,
+            pending_owner: Pubkey::default(),
+            attestation_counts: [0; 4],
+            child_accounts: 0,
+            token_ledger_count: 0,
+            attestation_accounts: 0,
+            reserved: [0; 3],
+        }
+    }
+}
+
+#[event]
+pub struct RevenueDepositedEvent {
+    pub registry: Pubkey,
+    pub agent_id: String,
+    pub depositor: Pubkey,
+    pub amount: u64,
+    /// False when the depositor is not authorized and the deposit is a tip
+    pub trusted: bool,
+    /// Free-form memo or off-chain reference id
+    pub memo: String,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OwnershipTransferStartedEvent {
+    pub registry: Pubkey,
+    pub owner: Pubkey,
+    pub pending_owner: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OwnershipTransferredEvent {
+    pub registry: Pubkey,
+    pub previous_owner: Pubkey,
+    pub new_owner: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OwnershipTransferCancelledEvent {
+    pub registry: Pubkey,
+    pub owner: Pubkey,
+    pub pending_owner: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AgentClosedEvent {
+    pub registry: Pubkey,
+    pub agent_id: String,
+    pub owner: Pubkey,
+    /// When the id can be registered again by anyone
+    pub reusable_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AttestationIssuedEvent {
+    pub registry: Pubkey,
+    pub attester: Pubkey,
+    pub attestation: Pubkey,
+    pub kind: AttestationKind,
+    pub reference: Pubkey,
+    pub expires_at: i64,
+    /// The agent's reputation including this attestation
+    pub reputation: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AttestationRevokedEvent {
+    pub registry: Pubkey,
+    pub attester: Pubkey,
+    pub attestation: Pubkey,
+    pub kind: AttestationKind,
+    /// The agent's reputation after the revocation
+    pub reputation: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AgentLinkedEvent {
+    pub registry: Pubkey,
+    pub agent_state: Pubkey,
+    pub token_mint: Pubkey,
+    pub source: CreationSource,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MetadataUpdatedEvent {
+    pub registry: Pubkey,
+    pub category: AgentCategory,
+    pub uri: String,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AgentListedEvent {
+    pub registry: Pubkey,
+    pub seller: Pubkey,
+    pub price: u64,
+    pub currency: Pubkey,
+    pub revenue_policy: ListingRevenuePolicy,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AgentDelistedEvent {
+    pub registry: Pubkey,
+    pub seller: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AgentSoldEvent {
+    pub registry: Pubkey,
+    pub seller: Pubkey,
+    pub buyer: Pubkey,
+    pub price: u64,
+    pub currency: Pubkey,
+    pub fee: u64,
+    /// Unclaimed revenue paid to the seller as part of the sale
+    pub revenue_settled: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TokenRevenueDepositedEvent {
+    pub registry: Pubkey,
+    pub mint: Pubkey,
+    pub depositor: Pubkey,
+    pub amount: u64,
+    pub trusted: bool,
+    pub memo: String,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TokenRevenueClaimedEvent {
+    pub registry: Pubkey,
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TokenSplitRevenueClaimedEvent {
+    pub registry: Pubkey,
+    pub mint: Pubkey,
+    pub beneficiary: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RevenueSplitProposedEvent {
+    pub registry: Pubkey,
+    pub shares: Vec<BeneficiaryShare>,
+    pub eta: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RevenueSplitAppliedEvent {
+    pub registry: Pubkey,
+    pub shares: Vec<BeneficiaryShare>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SplitRevenueClaimedEvent {
+    pub registry: Pubkey,
+    pub beneficiary: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Agent ID cannot be longer than 32 characters")]
+    AgentIdTooLong,
+    #[msg("Name cannot be longer than 64 characters")]
+    NameTooLong,
+    #[msg("Insufficient revenue to claim")]
+    InsufficientRevenue,
+    #[msg("No revenue available to claim")]
+    NoRevenueToClaim,
+    #[msg("Insufficient balance in registry")]
+    InsufficientBalance,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+    #[msg("Account is already on the current layout")]
+    AlreadyMigrated,
+    #[msg("Account cannot be migrated")]
+    InvalidMigrationAccount,
+    #[msg("Amount must be greater than zero")]
+    InvalidAmount,
+    #[msg("Memo cannot be longer than 64 characters")]
+    MemoTooLong,
+    #[msg("Depositor is already authorized")]
+    DepositorAlreadyAuthorized,
+    #[msg("Depositor is not authorized")]
+    DepositorNotAuthorized,
+    #[msg("Too many revenue depositors")]
+    TooManyDepositors,
+    #[msg("Split needs 1-8 distinct beneficiaries with weights summing to 10000 bps")]
+    InvalidRevenueSplit,
+    #[msg("No revenue split is pending")]
+    NoPendingSplit,
+    #[msg("Revenue split timelock has not expired")]
+    TimelockNotExpired,
+    #[msg("Claim outstanding revenue before enabling a split")]
+    UnclaimedRevenue,
+    #[msg("Too many removed beneficiaries with unclaimed revenue; settle them first")]
+    TooManySplitEntries,
+    #[msg("Signer is not a beneficiary")]
+    NotABeneficiary,
+    #[msg("Revenue is claimed through the revenue split")]
+    RevenueSplitActive,
+    #[msg("New owner must differ from the current owner")]
+    InvalidNewOwner,
+    #[msg("No ownership transfer is pending")]
+    NoPendingTransfer,
+    #[msg("Signer is not the pending owner")]
+    NotPendingOwner,
+    #[msg("Marketplace fee exceeds the maximum")]
+    InvalidMarketplaceFee,
+    #[msg("Listing seller no longer owns the agent")]
+    StaleListing,
+    #[msg("Listing price exceeds the buyer's maximum")]
+    PriceExceedsMax,
+    #[msg("Token accounts for the listing currency are missing or invalid")]
+    MissingTokenAccounts,
+    #[msg("Metadata URI cannot be longer than 200 characters")]
+    UriTooLong,
+    #[msg("At most 5 tags of 1-32 characters")]
+    InvalidTags,
+    #[msg("Agent is already in this category")]
+    CategoryUnchanged,
+    #[msg("Owner index entry is already current")]
+    IndexUpToDate,
+    #[msg("Agent state is not controlled by the registry owner")]
+    AgentStateOwnerMismatch,
+    #[msg("Signer is not the registry's factory")]
+    UnauthorizedFactory,
+    #[msg("Only the program upgrade authority can do this")]
+    UnauthorizedAdmin,
+    #[msg("Linked agent state and mint cannot be changed")]
+    LinkedAccountsImmutable,
+    #[msg("Attester name must be between 1 and 32 characters")]
+    InvalidAttesterName,
+    #[msg("Attester is not active")]
+    AttesterInactive,
+    #[msg("Expiry must be in the future, or 0 for none")]
+    InvalidExpiry,
+    #[msg("Attestation already revoked")]
+    AttestationRevoked,
+    #[msg("Attestation no longer counts towards reputation")]
+    AttestationInactive,
+    #[msg("Attestation has not expired")]
+    AttestationNotExpired,
+    #[msg("Attestation still counts towards reputation")]
+    AttestationActive,
+    #[msg("Cooldown out of range, or tombstone missing for a cooldown")]
+    InvalidCooldown,
+    #[msg("Agent is listed for sale")]
+    AgentListed,
+    #[msg("Accounts tied to the agent must be closed with it")]
+    MissingChildAccount,
+    #[msg("Revenue must be claimed first")]
+    RevenueOutstanding,
+    #[msg("Agent id is held by a tombstone")]
+    AgentIdTombstoned,
+    #[msg("Attestations on the agent must be closed first")]
+    AttestationsOpen,
+    #[msg("Agent id must be lowercase letters, digits and single dashes")]
+    InvalidAgentId,
+    #[msg("Agent id is reserved")]
+    AgentIdReserved,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn agent_ids_must_be_canonical() {
+        for valid in ["a", "my-agent", "agent-007", "0", &"a".repeat(MAX_AGENT_ID_LEN)] {
+            assert!(validate_agent_id(valid).is_ok(), "{valid}");
+        }
+
+        let too_long = "a".repeat(MAX_AGENT_ID_LEN + 1);
+        assert_eq!(
+            validate_agent_id(&too_long).unwrap_err(),
+            error!(ErrorCode::AgentIdTooLong)
+        );
+
+        for invalid in [
+            "",
+            "MyAgent",
+            "myAgent",
+            "-agent",
+            "agent-",
+            "my--agent",
+            "-",
+            "my agent",
+            "my_agent",
+            "my.agent",
+            "\u{430}gent",
+            "agent\u{200b}",
+            "agent\u{1f916}",
+            "\u{ff41}gent",
+        ] {
+            assert_eq!(
+                validate_agent_id(invalid).unwrap_err(),
+                error!(ErrorCode::InvalidAgentId),
+                "{invalid:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn legacy_ids_reserve_their_lowercase_form() {
+        assert_eq!(canonical_agent_id("MyAgent").as_deref(), Some("myagent"));
+        assert_eq!(canonical_agent_id("Agent-7").as_deref(), Some("agent-7"));
+        assert_eq!(canonical_agent_id("myagent"), None);
+        assert_eq!(canonical_agent_id("My Agent"), None);
+        assert_eq!(canonical_agent_id("My--Agent"), None);
+    }
+
+    #[test]
+    fn token_ledgers_settle_each_beneficiary_from_its_checkpoint() {
+        let (alice, bob) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut ledger = TokenRevenueLedger {
+            registry: Pubkey::default(),
+            mint: Pubkey::default(),
+            vault: Pubkey::default(),
+            total_revenue: 1_000,
+            claimed_revenue: 1_000,
+            total_tips: 0,
+            claimed_tips: 0,
+            bump: 0,
+            split_checkpoint: 1_000,
+            split_accruals: Vec::new(),
+        };
+
+        // Revenue from before the split took effect is never shared out
+        ledger.total_revenue += 101;
+        let alice_index = ledger.settle_beneficiary(alice, 7_000).unwrap();
+        assert_eq!(ledger.split_accruals[alice_index].accrued, 70);
+
+        let bob_index = ledger.settle_beneficiary(bob, 3_000).unwrap();
+        assert_eq!(ledger.split_accruals[bob_index].accrued, 30);
+
+        // Settling again without new revenue credits nothing more
+        ledger.total_revenue += 10;
+        ledger.settle_beneficiary(alice, 7_000).unwrap();
+        ledger.settle_beneficiary(alice, 7_000).unwrap();
+        assert_eq!(ledger.split_accruals[alice_index].accrued, 77);
+        assert_eq!(ledger.split_accruals[alice_index].checkpoint, 1_111);
+    }
 }